@@ -1,14 +1,18 @@
-use swil::{WindowConfig, Window, event::Event};
+use swil::{WindowConfig, Window, EventLoop, ControlFlow, event::{Event, WindowEvent}};
 
 fn main() {
+    let event_loop = EventLoop::new().unwrap();
+
     let config = WindowConfig::new();
-    let window = Window::new(&config).unwrap();
+    let window = Window::new(&event_loop, &config).unwrap();
 
-    window.event_loop(|event, exit| {
+    event_loop.run(|event, control_flow| {
         println!("{event:?}");
 
-        if let Event::CloseRequested = event {
-            *exit = true;
+        if let Event::WindowEvent { window_id, event: WindowEvent::CloseRequested } = event {
+            if window_id == window.id() {
+                *control_flow = ControlFlow::Exit;
+            }
         }
     }).unwrap();
-}
\ No newline at end of file
+}
@@ -5,108 +5,435 @@
 //! different DPIs. The scale factor respects OS wide UI scale settings
 //! 
 //! The [`PhysicalPosition`]/[`PhysicalSize`] types represent physical pixels on the
-//! screen. The ['LogicalPosition']/['LogicalSize'] types correspond to physical
+//! screen. The [`LogicalPosition`]/[`LogicalSize`] types correspond to physical
 //! pixel units divided by the scale factor. By using the logical types when creating
 //! UI elements, you can ensure that the UI is consistently sized on different screens
 //! 
+//! All of these types are generic over a [`Pixel`] type. Physical positions default to
+//! `i32`, since positions on multi monitor setups can be negative, physical sizes
+//! default to `u32` and logical types default to `f32`. Use `cast()` to convert
+//! between pixel types
+//! 
 //! # Scale Factor Calculation
 //! Swil tries to calculate the scale factor similarly to `winit`
-//! - **X11**: 
-//!   + Swil tries to read `Xft.dpi` from the `Xresources` resource database. The scale
-//!     factor will be `Xft.dpi / 96`
-//!   + If `Xft.dpi` is not present, calculate the DPI using the screen dimensions
-//!     reported by the X server
+//! - **All platforms**:
+//!   + A scale factor set with [`WindowConfig::scale_factor_override()`](crate::WindowConfig::scale_factor_override)
+//!     is always used for that window
+//!   + The `SWIL_SCALE_FACTOR` environment variable overrides the scale factor of every
+//!     monitor, for example `SWIL_SCALE_FACTOR=2.0`. Monitors can be given their own scale
+//!     factor by name, for example `SWIL_SCALE_FACTOR="HDMI-1=2.0;eDP-1=1.5"`. An entry
+//!     without a name applies to monitors that aren't listed
+//! - **X11**:
+//!   + Swil tries to read `Xft.dpi` from the `Xresources` resource database, or
+//!     `Xft/DPI` and `Gdk/WindowScalingFactor` from the XSETTINGS manager. The scale
+//!     factor will be `Xft.dpi / 96`, and is updated when the desktop settings change
+//!   + If neither is present, the DPI of each monitor is calculated from its
+//!     dimensions reported by RandR, so windows on different monitors can have
+//!     different scale factors
+
+use std::ops::{Add, Sub, Mul, Div, AddAssign, SubAssign};
+
+/// Where a scale factor came from
+/// 
+/// See the [module level docs](self#scale-factor-calculation) for how the scale factor
+/// is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScaleFactorSource {
+    /// Set with [`WindowConfig::scale_factor_override()`](crate::WindowConfig::scale_factor_override)
+    Override,
+
+    /// Set with the `SWIL_SCALE_FACTOR` environment variable
+    Environment,
+
+    /// Read from `Xft.dpi` or the XSETTINGS manager
+    Xft,
+
+    /// Calculated from the monitor's physical size reported by RandR
+    RandR,
+
+    /// None of the other sources were available, so the scale factor is `1.0`
+    Default
+}
+
+/// A numeric type that can be used for pixel values
+/// 
+/// Conversions between pixel types round to the nearest value, and values outside the
+/// range of an integer type are clamped to it
+pub trait Pixel: Copy + Into<f64> {
+    /// Converts from an `f64`, rounding for integer types
+    fn from_f64(value: f64) -> Self;
+
+    /// Converts to another pixel type
+    fn cast<P: Pixel>(self) -> P {
+        P::from_f64(self.into())
+    }
+}
+
+macro_rules! impl_pixel {
+    ($($ty:ty),*) => {
+        $(
+            impl Pixel for $ty {
+                fn from_f64(value: f64) -> Self {
+                    value.round() as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_pixel!(u8, u16, u32, i8, i16, i32);
+
+impl Pixel for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+impl Pixel for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
 
 /// A position that is either physical or logical
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Position {
     Physical(PhysicalPosition),
     Logical(LogicalPosition)
 }
 
+impl Position {
+    /// Converts to a physical position, using the scale factor if the position is logical
+    pub fn to_physical<P: Pixel>(&self, scale_factor: f32) -> PhysicalPosition<P> {
+        match self {
+            Position::Physical(position) => position.cast(),
+            Position::Logical(position) => position.to_physical(scale_factor)
+        }
+    }
+
+    /// Converts to a logical position, using the scale factor if the position is physical
+    pub fn to_logical<P: Pixel>(&self, scale_factor: f32) -> LogicalPosition<P> {
+        match self {
+            Position::Physical(position) => position.to_logical(scale_factor),
+            Position::Logical(position) => position.cast()
+        }
+    }
+}
+
+impl<P: Pixel> From<PhysicalPosition<P>> for Position {
+    fn from(position: PhysicalPosition<P>) -> Self {
+        Position::Physical(position.cast())
+    }
+}
+
+impl<P: Pixel> From<LogicalPosition<P>> for Position {
+    fn from(position: LogicalPosition<P>) -> Self {
+        Position::Logical(position.cast())
+    }
+}
+
 /// A size that is either physical or logical
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Size {
     Physical(PhysicalSize),
     Logical(LogicalSize)
 }
 
-/// A position in physical pixel units
-#[derive(Debug, Clone)]
-pub struct PhysicalPosition {
-    pub x: u32,
-    pub y: u32
+impl Size {
+    /// Converts to a physical size, using the scale factor if the size is logical
+    pub fn to_physical<P: Pixel>(&self, scale_factor: f32) -> PhysicalSize<P> {
+        match self {
+            Size::Physical(size) => size.cast(),
+            Size::Logical(size) => size.to_physical(scale_factor)
+        }
+    }
+
+    /// Converts to a logical size, using the scale factor if the size is physical
+    pub fn to_logical<P: Pixel>(&self, scale_factor: f32) -> LogicalSize<P> {
+        match self {
+            Size::Physical(size) => size.to_logical(scale_factor),
+            Size::Logical(size) => size.cast()
+        }
+    }
+}
+
+impl<P: Pixel> From<PhysicalSize<P>> for Size {
+    fn from(size: PhysicalSize<P>) -> Self {
+        Size::Physical(size.cast())
+    }
 }
 
-impl PhysicalPosition {
-    pub fn from_logical(position: LogicalPosition, scale_factor: f32) -> Self {
-        let x = (position.x * scale_factor).round() as u32;
-        let y = (position.y * scale_factor).round() as u32;
+impl<P: Pixel> From<LogicalSize<P>> for Size {
+    fn from(size: LogicalSize<P>) -> Self {
+        Size::Logical(size.cast())
+    }
+}
 
+/// A position in physical pixel units
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalPosition<P = i32> {
+    pub x: P,
+    pub y: P
+}
+
+impl<P> PhysicalPosition<P> {
+    pub const fn new(x: P, y: P) -> Self {
         Self { x, y }
     }
+}
 
-    pub fn to_logical(&self, scale_factor: f32) -> LogicalPosition {
-        let x = self.x as f32 / scale_factor;
-        let y = self.y as f32 / scale_factor;
+impl<P: Pixel> PhysicalPosition<P> {
+    pub fn from_logical<X: Pixel>(position: LogicalPosition<X>, scale_factor: f32) -> Self {
+        position.to_physical(scale_factor)
+    }
 
-        LogicalPosition { x, y }
+    pub fn to_logical<X: Pixel>(&self, scale_factor: f32) -> LogicalPosition<X> {
+        let x = self.x.into() / scale_factor as f64;
+        let y = self.y.into() / scale_factor as f64;
+
+        LogicalPosition::new(x, y).cast()
+    }
+
+    pub fn cast<X: Pixel>(&self) -> PhysicalPosition<X> {
+        PhysicalPosition::new(self.x.cast(), self.y.cast())
     }
 }
 
 /// A size in physical pixel units
-#[derive(Debug, Clone)]
-pub struct PhysicalSize {
-    pub width: u32,
-    pub height: u32
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalSize<P = u32> {
+    pub width: P,
+    pub height: P
 }
 
-impl PhysicalSize {
-    pub fn from_logical(size: LogicalSize, scale_factor: f32) -> Self {
-        let width = (size.width * scale_factor).round() as u32;
-        let height = (size.height * scale_factor).round() as u32;
-
+impl<P> PhysicalSize<P> {
+    pub const fn new(width: P, height: P) -> Self {
         Self { width, height }
     }
+}
 
-    pub fn to_logical(&self, scale_factor: f32) -> LogicalSize {
-        let width = self.width as f32 / scale_factor;
-        let height = self.height as f32 / scale_factor;
+impl<P: Pixel> PhysicalSize<P> {
+    pub fn from_logical<X: Pixel>(size: LogicalSize<X>, scale_factor: f32) -> Self {
+        size.to_physical(scale_factor)
+    }
 
-        LogicalSize { width, height }
+    pub fn to_logical<X: Pixel>(&self, scale_factor: f32) -> LogicalSize<X> {
+        let width = self.width.into() / scale_factor as f64;
+        let height = self.height.into() / scale_factor as f64;
+
+        LogicalSize::new(width, height).cast()
+    }
+
+    pub fn cast<X: Pixel>(&self) -> PhysicalSize<X> {
+        PhysicalSize::new(self.width.cast(), self.height.cast())
     }
 }
 
 /// A position in scaled logical units
-#[derive(Debug, Clone)]
-pub struct LogicalPosition {
-    pub x: f32,
-    pub y: f32
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogicalPosition<P = f32> {
+    pub x: P,
+    pub y: P
+}
+
+impl<P> LogicalPosition<P> {
+    pub const fn new(x: P, y: P) -> Self {
+        Self { x, y }
+    }
 }
 
-impl LogicalPosition {
-    pub fn from_physical(position: PhysicalPosition, scale_factor: f32) -> Self {
+impl<P: Pixel> LogicalPosition<P> {
+    pub fn from_physical<X: Pixel>(position: PhysicalPosition<X>, scale_factor: f32) -> Self {
         position.to_logical(scale_factor)
     }
 
-    pub fn to_physical(&self, scale_factor: f32) -> PhysicalPosition {
-        PhysicalPosition::from_logical(self.clone(), scale_factor)
+    pub fn to_physical<X: Pixel>(&self, scale_factor: f32) -> PhysicalPosition<X> {
+        let x = self.x.into() * scale_factor as f64;
+        let y = self.y.into() * scale_factor as f64;
+
+        PhysicalPosition::new(x, y).cast()
+    }
+
+    pub fn cast<X: Pixel>(&self) -> LogicalPosition<X> {
+        LogicalPosition::new(self.x.cast(), self.y.cast())
     }
 }
 
 /// A size in scaled logical units
-#[derive(Debug, Clone)]
-pub struct LogicalSize {
-    pub width: f32,
-    pub height: f32
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogicalSize<P = f32> {
+    pub width: P,
+    pub height: P
+}
+
+impl<P> LogicalSize<P> {
+    pub const fn new(width: P, height: P) -> Self {
+        Self { width, height }
+    }
 }
 
-impl LogicalSize {
-    pub fn from_physical(size: PhysicalSize, scale_factor: f32) -> Self {
+impl<P: Pixel> LogicalSize<P> {
+    pub fn from_physical<X: Pixel>(size: PhysicalSize<X>, scale_factor: f32) -> Self {
         size.to_logical(scale_factor)
     }
 
-    pub fn to_physical(&self, scale_factor: f32) -> PhysicalSize {
-        PhysicalSize::from_logical(self.clone(), scale_factor)
+    pub fn to_physical<X: Pixel>(&self, scale_factor: f32) -> PhysicalSize<X> {
+        let width = self.width.into() * scale_factor as f64;
+        let height = self.height.into() * scale_factor as f64;
+
+        PhysicalSize::new(width, height).cast()
+    }
+
+    pub fn cast<X: Pixel>(&self) -> LogicalSize<X> {
+        LogicalSize::new(self.width.cast(), self.height.cast())
     }
-}
\ No newline at end of file
+}
+
+// Arithmetic operators and tuple/array conversions, which work the same for every type
+macro_rules! impl_vector {
+    ($ty:ident, $a:ident, $b:ident) => {
+        impl<P: Add<Output = P>> Add for $ty<P> {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                $ty { $a: self.$a + other.$a, $b: self.$b + other.$b }
+            }
+        }
+
+        impl<P: Sub<Output = P>> Sub for $ty<P> {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                $ty { $a: self.$a - other.$a, $b: self.$b - other.$b }
+            }
+        }
+
+        impl<P: Mul<Output = P> + Copy> Mul<P> for $ty<P> {
+            type Output = Self;
+
+            fn mul(self, scalar: P) -> Self {
+                $ty { $a: self.$a * scalar, $b: self.$b * scalar }
+            }
+        }
+
+        impl<P: Div<Output = P> + Copy> Div<P> for $ty<P> {
+            type Output = Self;
+
+            fn div(self, scalar: P) -> Self {
+                $ty { $a: self.$a / scalar, $b: self.$b / scalar }
+            }
+        }
+
+        impl<P: AddAssign> AddAssign for $ty<P> {
+            fn add_assign(&mut self, other: Self) {
+                self.$a += other.$a;
+                self.$b += other.$b;
+            }
+        }
+
+        impl<P: SubAssign> SubAssign for $ty<P> {
+            fn sub_assign(&mut self, other: Self) {
+                self.$a -= other.$a;
+                self.$b -= other.$b;
+            }
+        }
+
+        impl<P: Pixel, X: Pixel> From<(X, X)> for $ty<P> {
+            fn from((a, b): (X, X)) -> Self {
+                $ty { $a: a.cast(), $b: b.cast() }
+            }
+        }
+
+        impl<P: Pixel, X: Pixel> From<$ty<P>> for (X, X) {
+            fn from(value: $ty<P>) -> Self {
+                (value.$a.cast(), value.$b.cast())
+            }
+        }
+
+        impl<P: Pixel, X: Pixel> From<[X; 2]> for $ty<P> {
+            fn from([a, b]: [X; 2]) -> Self {
+                $ty { $a: a.cast(), $b: b.cast() }
+            }
+        }
+
+        impl<P: Pixel, X: Pixel> From<$ty<P>> for [X; 2] {
+            fn from(value: $ty<P>) -> Self {
+                [value.$a.cast(), value.$b.cast()]
+            }
+        }
+    };
+}
+
+impl_vector!(PhysicalPosition, x, y);
+impl_vector!(PhysicalSize, width, height);
+impl_vector!(LogicalPosition, x, y);
+impl_vector!(LogicalSize, width, height);
+
+/// A rectangle in physical pixel units
+/// 
+/// Rects describing parts of a window, such as redraw damage and window shapes, are
+/// relative to the top left corner of the window. Other coordinate spaces are
+/// documented where they're used
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    pub position: PhysicalPosition,
+    pub size: PhysicalSize
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    fn round_trip<T>(value: T, json: &str)
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned + PartialEq + std::fmt::Debug
+    {
+        assert_eq!(serde_json::to_string(&value).unwrap(), json);
+        assert_eq!(serde_json::from_str::<T>(json).unwrap(), value);
+    }
+
+    #[test]
+    fn physical_round_trip() {
+        round_trip(PhysicalPosition::new(-1920, -40), r#"{"x":-1920,"y":-40}"#);
+        round_trip(PhysicalSize::new(800, 600), r#"{"width":800,"height":600}"#);
+        round_trip(PhysicalPosition::<f64>::new(1.5, -2.5), r#"{"x":1.5,"y":-2.5}"#);
+    }
+
+    #[test]
+    fn logical_round_trip() {
+        round_trip(LogicalPosition::new(-12.5, 4.0), r#"{"x":-12.5,"y":4.0}"#);
+        round_trip(LogicalSize::new(750.0, 500.0), r#"{"width":750.0,"height":500.0}"#);
+    }
+
+    #[test]
+    fn position_size_round_trip() {
+        round_trip(Position::Physical(PhysicalPosition::new(-5, 10)), r#"{"Physical":{"x":-5,"y":10}}"#);
+        round_trip(Position::Logical(LogicalPosition::new(2.5, 3.0)), r#"{"Logical":{"x":2.5,"y":3.0}}"#);
+        round_trip(Size::Physical(PhysicalSize::new(640, 480)), r#"{"Physical":{"width":640,"height":480}}"#);
+        round_trip(Size::Logical(LogicalSize::new(320.0, 240.0)), r#"{"Logical":{"width":320.0,"height":240.0}}"#);
+    }
+
+    #[test]
+    fn rect_round_trip() {
+        let rect = Rect {
+            position: PhysicalPosition::new(-10, 20),
+            size: PhysicalSize::new(30, 40)
+        };
+
+        round_trip(rect, r#"{"position":{"x":-10,"y":20},"size":{"width":30,"height":40}}"#);
+    }
+
+    #[test]
+    fn scale_factor_source_round_trip() {
+        round_trip(ScaleFactorSource::Override, r#""Override""#);
+        round_trip(ScaleFactorSource::RandR, r#""RandR""#);
+    }
+}
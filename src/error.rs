@@ -31,14 +31,63 @@ pub enum Error {
     #[cfg(unix)]
     X11SetSizeHintsFailed(ConnectionError),
 
+    #[cfg(unix)]
+    X11SyncCounterFailed(ConnectionError),
+
+    #[cfg(unix)]
+    X11PresentFailed(ConnectionError),
+
     #[cfg(unix)]
     X11MapWindowFailed(ConnectionError),
 
+    #[cfg(unix)]
+    X11ConfigureWindowFailed(ConnectionError),
+
+    #[cfg(unix)]
+    X11SelectInputFailed(ConnectionError),
+
+    #[cfg(unix)]
+    X11ExtensionQueryFailed(ConnectionError),
+
+    #[cfg(unix)]
+    X11ExtensionMissing(&'static str),
+
+    #[cfg(unix)]
+    X11SetShapeFailed(ConnectionError),
+
+    #[cfg(unix)]
+    X11SetOpacityFailed(ConnectionError),
+
+    #[cfg(unix)]
+    X11SetBlurFailed(ConnectionError),
+
+    #[cfg(unix)]
+    X11QueryPointerFailed(ReplyError),
+
+    #[cfg(unix)]
+    X11MonitorQueryFailed(ReplyError),
+
+    #[cfg(unix)]
+    X11TranslateCoordinatesFailed(ReplyError),
+
+    #[cfg(unix)]
+    X11UngrabPointerFailed(ConnectionError),
+
+    #[cfg(unix)]
+    X11SendEventFailed(ConnectionError),
+
     #[cfg(unix)]
     X11FlushFailed(ConnectionError),
 
     #[cfg(unix)]
-    X11WaitForEventFailed(ConnectionError)
+    X11PollForEventFailed(ConnectionError),
+
+    #[cfg(unix)]
+    PollFailed(std::io::Error),
+
+    EventLoopClosed,
+
+    WindowDestroyed
 }
 
 impl Display for Error {
@@ -53,9 +102,26 @@ impl Display for Error {
             Self::X11SetTitleFailed(err) => write!(f, "Failed to set window title ({err})"),
             Self::X11WindowCloseHookFailed(err) => write!(f, "Failed to hook window close event ({err})"),
             Self::X11SetSizeHintsFailed(err) => write!(f, "Failed to set window size hints ({err})"),
+            Self::X11SyncCounterFailed(err) => write!(f, "Failed to update sync counter ({err})"),
+            Self::X11PresentFailed(err) => write!(f, "Failed to request frame timing ({err})"),
             Self::X11MapWindowFailed(err) => write!(f, "Failed to map window ({err})"),
+            Self::X11ConfigureWindowFailed(err) => write!(f, "Failed to configure window ({err})"),
+            Self::X11SelectInputFailed(err) => write!(f, "Failed to select window events ({err})"),
+            Self::X11ExtensionQueryFailed(err) => write!(f, "Failed to query X11 extension ({err})"),
+            Self::X11ExtensionMissing(name) => write!(f, "X11 server does not support the {name} extension"),
+            Self::X11SetShapeFailed(err) => write!(f, "Failed to set window shape ({err})"),
+            Self::X11SetOpacityFailed(err) => write!(f, "Failed to set window opacity ({err})"),
+            Self::X11SetBlurFailed(err) => write!(f, "Failed to set window blur region ({err})"),
+            Self::X11QueryPointerFailed(err) => write!(f, "Failed to query pointer ({err})"),
+            Self::X11MonitorQueryFailed(err) => write!(f, "Failed to query monitors ({err})"),
+            Self::X11TranslateCoordinatesFailed(err) => write!(f, "Failed to translate coordinates ({err})"),
+            Self::X11UngrabPointerFailed(err) => write!(f, "Failed to ungrab pointer ({err})"),
+            Self::X11SendEventFailed(err) => write!(f, "Failed to send event ({err})"),
             Self::X11FlushFailed(err) => write!(f, "Failed to flush X11 connection ({err})"),
-            Self::X11WaitForEventFailed(err) => write!(f, "Failed to wait for event ({err})")
+            Self::X11PollForEventFailed(err) => write!(f, "Failed to poll for event ({err})"),
+            Self::PollFailed(err) => write!(f, "Failed to wait for connection to become readable ({err})"),
+            Self::EventLoopClosed => write!(f, "The event loop no longer exists"),
+            Self::WindowDestroyed => write!(f, "The window has been destroyed")
         }
     }
 }
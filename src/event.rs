@@ -1,7 +1,13 @@
-use crate::dpi::{PhysicalSize, PhysicalPosition};
+use std::borrow::Cow;
+use std::time::Duration;
+use std::sync::{Arc, Mutex};
+
+use crate::{WindowId, FrameExtents};
+use crate::dpi::{PhysicalSize, PhysicalPosition, Rect};
 
 /// The state of a button event
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ButtonState {
     Pressed,
     Released
@@ -10,15 +16,28 @@ pub enum ButtonState {
 pub type KeyCode = u8;
 
 /// A keyboard input event
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyboardInput<'a> {
     pub code: KeyCode,
     pub state: ButtonState,
-    pub text: Option<&'a str>
+    pub text: Option<Cow<'a, str>>
+}
+
+impl<'a> KeyboardInput<'a> {
+    /// Converts into a `'static` keyboard input event that owns its text
+    pub fn into_owned(self) -> KeyboardInput<'static> {
+        KeyboardInput {
+            code: self.code,
+            state: self.state,
+            text: self.text.map(|text| Cow::Owned(text.into_owned()))
+        }
+    }
 }
 
 /// A mouse scroll event
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MouseScroll {
     Up,
     Down,
@@ -27,7 +46,8 @@ pub enum MouseScroll {
 }
 
 /// A mouse button
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MouseButton {
     Left,
     Middle,
@@ -36,20 +56,194 @@ pub enum MouseButton {
 }
 
 /// A mouse button input event
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct  MouseInput {
     pub button: MouseButton,
     pub state: ButtonState
 }
 
-/// An event recieved by a window
+/// An event recieved by the event loop
+/// 
+/// `T` is the type of user events sent through an
+/// [`EventLoopProxy`](crate::EventLoopProxy)
+/// 
+/// Events may borrow data from the event loop, use [`Event::into_owned()`] to get
+/// an [`OwnedEvent`] that can be stored or sent to other threads
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event<'a, T = ()> {
+    /// An event recieved by one of the event loop's windows
+    WindowEvent {
+        window_id: WindowId,
+        event: WindowEvent<'a>
+    },
+
+    /// A user event sent using [`EventLoopProxy::send_event()`](crate::EventLoopProxy::send_event)
+    User(T),
+
+    /// The event loop has processed all pending events and is about to wait for new ones
+    /// 
+    /// This is a good place to run per frame application logic. How long the event loop
+    /// waits afterwards is decided by the [`ControlFlow`](crate::ControlFlow). With
+    /// [`ControlFlow::Poll`](crate::ControlFlow::Poll) this event is sent continuously
+    AboutToWait,
+
+    /// Monitors have been connected, disconnected or reconfigured
+    /// 
+    /// Use [`EventLoop::available_monitors()`](crate::EventLoop::available_monitors) to
+    /// get the new configuration. Windows whose scale factor changed as a result also
+    /// recieve a [`WindowEvent::ScaleFactorChanged`] event
+    MonitorsChanged
+}
+
+impl<'a, T> Event<'a, T> {
+    /// Converts into an [`OwnedEvent`] that doesn't borrow any data
+    pub fn into_owned(self) -> OwnedEvent<T> {
+        match self {
+            Self::WindowEvent { window_id, event } => Event::WindowEvent { window_id, event: event.into_owned() },
+            Self::User(event) => Event::User(event),
+            Self::AboutToWait => Event::AboutToWait,
+            Self::MonitorsChanged => Event::MonitorsChanged
+        }
+    }
+}
+
+/// An [`Event`] that doesn't borrow any data
+/// 
+/// Owned events are `Send` as long as `T` is, so they can be queued, recorded or
+/// forwarded to other threads
+pub type OwnedEvent<T = ()> = Event<'static, T>;
+
+// Make sure owned events stay sendable
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<OwnedEvent>();
+};
+
+/// The new size of a window after its scale factor changed
+/// 
+/// Sent with [`WindowEvent::ScaleFactorChanged`]. The window is resized to this size
+/// after the event has been handled, which the handler can change
 #[derive(Debug, Clone)]
-pub enum Event<'a> {
+pub struct SuggestedSize {
+    size: Arc<Mutex<PhysicalSize>>
+}
+
+impl SuggestedSize {
+    pub(crate) fn new(size: PhysicalSize) -> Self {
+        Self { size: Arc::new(Mutex::new(size)) }
+    }
+
+    /// Gets the size the window will be resized to
+    pub fn get(&self) -> PhysicalSize {
+        *self.size.lock().unwrap()
+    }
+
+    /// Sets the size the window will be resized to
+    pub fn set(&self, size: PhysicalSize) {
+        *self.size.lock().unwrap() = size;
+    }
+}
+
+impl PartialEq for SuggestedSize {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+// Suggested sizes are serialized as the size itself
+#[cfg(feature = "serde")]
+impl serde::Serialize for SuggestedSize {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(&self.get(), serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SuggestedSize {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <PhysicalSize as serde::Deserialize>::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// An event recieved by a window
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowEvent<'a> {
     /// Size of the window has changed
     /// 
     /// Contains the new physical size
     Resized(PhysicalSize),
 
+    /// The window needs to be redrawn
+    /// 
+    /// This is sent when parts of the window have been uncovered, or after
+    /// [`WindowHandle::request_redraw()`](crate::WindowHandle::request_redraw) is called. The
+    /// damaged regions of the window are collected so that at most one redraw is
+    /// requested per window per event loop iteration
+    /// 
+    /// The window should be drawn before the event handler returns, or when using an
+    /// async event stream, before the stream is polled again. During resizes, window
+    /// managers that support `_NET_WM_SYNC_REQUEST` wait until the event has been handled
+    /// before showing the new window size, which avoids flickering. Windows created with
+    /// [`WindowConfig::manual_frame_sync()`](crate::WindowConfig::manual_frame_sync) are
+    /// waited on until [`WindowHandle::frame_presented()`](crate::WindowHandle::frame_presented)
+    /// is called instead
+    RedrawRequested {
+        /// The regions of the window that need to be redrawn
+        damage: Vec<Rect>
+    },
+
+    /// A frame has been shown on screen
+    /// 
+    /// This is sent in response to [`WindowHandle::request_frame_callback()`](crate::WindowHandle::request_frame_callback)
+    /// at the next vertical blank. On X11 frames presented using the `Present` extension,
+    /// which includes most OpenGL and Vulkan drivers, also produce this event once frame
+    /// callbacks have been requested
+    FramePresented {
+        /// The media stream counter, which increases by one every vertical blank
+        msc: u64,
+
+        /// The time at which the frame was shown, in microseconds
+        ust: u64,
+
+        /// The estimated time between vertical blanks, if known
+        refresh_interval: Option<Duration>
+    },
+
+    /// A frame has been drawn by the compositor
+    /// 
+    /// On X11, this is sent by compositing window managers that support
+    /// `_NET_WM_FRAME_DRAWN`, after each frame synchronized with them has been drawn.
+    /// Applications can wait for it before drawing the next frame, to avoid drawing
+    /// frames the compositor can't keep up with
+    FrameDrawn {
+        /// The frame's value of the extended sync counter, which increases with every frame
+        frame: u64,
+
+        /// The time at which the compositor drew the frame, in microseconds
+        ust: u64
+    },
+
+    /// The window's scale factor has changed
+    /// 
+    /// This happens when the window is moved to a monitor with a different DPI. By
+    /// default the window is resized to keep its logical size, the event handler can
+    /// choose a different size using [`SuggestedSize::set()`]
+    ScaleFactorChanged {
+        /// The new scale factor
+        scale_factor: f32,
+
+        /// The size the window will be resized to once the event has been handled
+        suggested_size: SuggestedSize
+    },
+
+    /// The size of the window manager's decorations has changed
+    /// 
+    /// This is also sent once the window manager first decorates the window
+    FrameExtentsChanged(FrameExtents),
+
     /// The window has been requested to close
     /// 
     /// This can happen due to clicking the close button, `alt + f4`, etc
@@ -81,4 +275,100 @@ pub enum Event<'a> {
 
     /// A mouse button event has occurred
     MouseInput(MouseInput)
-}
\ No newline at end of file
+}
+
+impl<'a> WindowEvent<'a> {
+    /// Converts into a `'static` window event that doesn't borrow any data
+    pub fn into_owned(self) -> WindowEvent<'static> {
+        match self {
+            Self::Resized(size) => WindowEvent::Resized(size),
+            Self::RedrawRequested { damage } => WindowEvent::RedrawRequested { damage },
+            Self::FramePresented { msc, ust, refresh_interval } => WindowEvent::FramePresented { msc, ust, refresh_interval },
+            Self::FrameDrawn { frame, ust } => WindowEvent::FrameDrawn { frame, ust },
+            Self::ScaleFactorChanged { scale_factor, suggested_size } => WindowEvent::ScaleFactorChanged { scale_factor, suggested_size },
+            Self::FrameExtentsChanged(frame_extents) => WindowEvent::FrameExtentsChanged(frame_extents),
+            Self::CloseRequested => WindowEvent::CloseRequested,
+            Self::FocusChanged(focused) => WindowEvent::FocusChanged(focused),
+            Self::KeyboardInput(input) => WindowEvent::KeyboardInput(input.into_owned()),
+            Self::CursorMoved(position) => WindowEvent::CursorMoved(position),
+            Self::CursorEntered => WindowEvent::CursorEntered,
+            Self::CursorLeft => WindowEvent::CursorLeft,
+            Self::MouseScroll(scroll) => WindowEvent::MouseScroll(scroll),
+            Self::MouseInput(input) => WindowEvent::MouseInput(input)
+        }
+    }
+}
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    fn round_trip(event: OwnedEvent<String>) {
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(serde_json::from_str::<OwnedEvent<String>>(&json).unwrap(), event);
+    }
+
+    fn window_event(event: WindowEvent<'static>) -> OwnedEvent<String> {
+        Event::WindowEvent { window_id: WindowId(42), event }
+    }
+
+    #[test]
+    fn keyboard_input_round_trip() {
+        let input = KeyboardInput {
+            code: 38,
+            state: ButtonState::Pressed,
+            text: Some(Cow::Borrowed("a"))
+        };
+
+        let event = Event::WindowEvent { window_id: WindowId(42), event: WindowEvent::KeyboardInput(input) }.into_owned();
+        let json = serde_json::to_string(&event).unwrap();
+
+        assert_eq!(json, r#"{"WindowEvent":{"window_id":42,"event":{"KeyboardInput":{"code":38,"state":"Pressed","text":"a"}}}}"#);
+        assert_eq!(serde_json::from_str::<OwnedEvent<String>>(&json).unwrap(), event);
+
+        round_trip(window_event(WindowEvent::KeyboardInput(KeyboardInput {
+            code: 9,
+            state: ButtonState::Released,
+            text: None
+        })));
+    }
+
+    #[test]
+    fn scale_factor_changed_round_trip() {
+        let event = window_event(WindowEvent::ScaleFactorChanged {
+            scale_factor: 2.0,
+            suggested_size: SuggestedSize::new(PhysicalSize::new(1600, 1200))
+        });
+
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"WindowEvent":{"window_id":42,"event":{"ScaleFactorChanged":{"scale_factor":2.0,"suggested_size":{"width":1600,"height":1200}}}}}"#);
+
+        round_trip(event);
+    }
+
+    #[test]
+    fn mouse_round_trip() {
+        round_trip(window_event(WindowEvent::MouseInput(MouseInput { button: MouseButton::Other(8), state: ButtonState::Pressed })));
+        round_trip(window_event(WindowEvent::MouseScroll(MouseScroll::Left)));
+        round_trip(window_event(WindowEvent::CursorMoved(PhysicalPosition::new(-3, 7))));
+    }
+
+    #[test]
+    fn window_event_round_trip() {
+        round_trip(window_event(WindowEvent::Resized(PhysicalSize::new(800, 600))));
+        round_trip(window_event(WindowEvent::RedrawRequested {
+            damage: vec![Rect { position: PhysicalPosition::new(0, 0), size: PhysicalSize::new(10, 10) }]
+        }));
+        round_trip(window_event(WindowEvent::FramePresented { msc: 1, ust: 2, refresh_interval: Some(Duration::from_micros(16667)) }));
+        round_trip(window_event(WindowEvent::FrameDrawn { frame: 4, ust: 5 }));
+        round_trip(window_event(WindowEvent::FrameExtentsChanged(FrameExtents { left: 1, right: 2, top: 30, bottom: 4 })));
+        round_trip(window_event(WindowEvent::FocusChanged(true)));
+        round_trip(window_event(WindowEvent::CloseRequested));
+    }
+
+    #[test]
+    fn event_round_trip() {
+        round_trip(Event::User(String::from("user event")));
+        round_trip(Event::AboutToWait);
+        round_trip(Event::MonitorsChanged);
+    }
+}
@@ -0,0 +1,296 @@
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, AsFd, RawFd, BorrowedFd};
+
+#[cfg(feature = "async")]
+use std::{pin::Pin, future, task::{Context, Poll}};
+
+#[cfg(feature = "async")]
+use futures_core::Stream;
+
+use crate::{platform, Error, WindowId, MonitorHandle, event::{Event, WindowEvent}};
+
+#[cfg(feature = "async")]
+use crate::event::OwnedEvent;
+
+/// An event loop that recieves events for all windows created from it
+/// 
+/// The event loop owns the connection to the windowing system. Any number of
+/// [`Window`](crate::Window)s can be created from a single event loop, and
+/// they all share its connection
+/// 
+/// `T` is the type of user events that can be sent to the event loop from
+/// other threads using an [`EventLoopProxy`]
+pub struct EventLoop<T = ()> {
+    pub(crate) inner: platform::EventLoop<T>
+}
+
+impl EventLoop {
+    /// Create a new event loop
+    /// 
+    /// This connects to the windowing system. Use [`EventLoop::with_user_events()`]
+    /// to create an event loop that accepts user events of a type other than `()`
+    pub fn new() -> Result<Self, Error> {
+        Self::with_user_events()
+    }
+}
+
+impl<T> EventLoop<T> {
+    /// Create a new event loop that accepts user events of type `T`
+    /// 
+    /// This connects to the windowing system
+    pub fn with_user_events() -> Result<Self, Error> {
+        platform::EventLoop::new().map(|inner| Self { inner })
+    }
+
+    /// Gets all monitors connected to the system
+    /// 
+    /// On X11 monitors are queried using RandR 1.5, falling back to Xinerama. If neither
+    /// is available the whole screen is treated as a single monitor
+    pub fn available_monitors(&self) -> Vec<MonitorHandle> {
+        self.inner
+            .available_monitors()
+            .into_iter()
+            .map(|inner| MonitorHandle { inner })
+            .collect()
+    }
+
+    /// Gets the primary monitor, if the system has one
+    pub fn primary_monitor(&self) -> Option<MonitorHandle> {
+        self.inner.primary_monitor().map(|inner| MonitorHandle { inner })
+    }
+
+    /// Creates a proxy that can wake up the event loop and send it user events
+    pub fn create_proxy(&self) -> EventLoopProxy<T> {
+        EventLoopProxy { inner: self.inner.create_proxy() }
+    }
+
+    /// Runs the event loop
+    /// 
+    /// This function takes over the thread until the event loop exits. The given closure
+    /// is called to process each event. Window events are tagged with the
+    /// [`WindowId`](crate::WindowId) of the window (or child window) that recieved them.
+    /// Once all pending events are processed, the closure recieves
+    /// [`Event::AboutToWait`] before the event loop waits for more events
+    /// 
+    /// The closure also gets a `control_flow: &mut ControlFlow` parameter, which is
+    /// initially [`ControlFlow::Wait`]. It can be changed to control how the event loop
+    /// waits for new events, or set to [`ControlFlow::Exit`] to exit from the event loop.
+    /// The value is kept between calls of the closure
+    pub fn run(&self, func: impl FnMut(Event<T>, &mut ControlFlow)) -> Result<(), Error> {
+        self.inner.run(func)
+    }
+
+    /// Runs the event loop, passing events to the given [`ApplicationHandler`]
+    /// 
+    /// This behaves the same as [`EventLoop::run()`], but each kind of event is passed
+    /// to its own handler method. [`ApplicationHandler::exiting()`] is called once the
+    /// event loop exits, including when it exits due to an error
+    pub fn run_app(&self, handler: &mut impl ApplicationHandler<T>) -> Result<(), Error> {
+        let res = self.inner.run(|event, control_flow| match event {
+            Event::WindowEvent { window_id, event } => handler.window_event(window_id, event, control_flow),
+            Event::User(event) => handler.user_event(event, control_flow),
+            Event::AboutToWait => handler.about_to_wait(control_flow),
+            Event::MonitorsChanged => handler.monitors_changed(control_flow)
+        });
+
+        handler.exiting();
+        res
+    }
+
+    /// Processes pending events without taking over the thread
+    /// 
+    /// This is meant for applications such as games that run their own main loop.
+    /// All events that have already been recieved are passed to the given closure,
+    /// after which this function returns. If there are no pending events, it waits for
+    /// new events for at most `timeout`, where `None` waits indefinitely and a zero
+    /// timeout never waits
+    /// 
+    /// The returned [`PumpStatus`] reports whether any window was requested to close
+    pub fn pump_events(&self, timeout: Option<Duration>, func: impl FnMut(Event<T>)) -> Result<PumpStatus, Error> {
+        self.inner.pump_events(timeout, func)
+    }
+
+    /// Processes all pending events without blocking
+    /// 
+    /// This is meant for integrating the event loop into an external reactor, such as
+    /// `tokio` or `calloop`. Register the event loop's file descriptor (see
+    /// [`AsRawFd`]/[`AsFd`]) for read readiness, and call this whenever it becomes
+    /// readable. Also call it once after creating windows, since events may already
+    /// have been recieved while setting them up
+    /// 
+    /// Redraws requested while handling events are delivered before this returns.
+    /// Redraws requested while handling a
+    /// [`WindowEvent::RedrawRequested`](crate::event::WindowEvent::RedrawRequested) event
+    /// are delivered on the next call, and wake up the event loop's file descriptor so
+    /// the reactor calls this again
+    pub fn dispatch_pending(&self, func: impl FnMut(Event<T>)) -> Result<(), Error> {
+        self.inner.dispatch_pending(func)
+    }
+
+    /// Returns a stream of events recieved by the event loop
+    /// 
+    /// The stream is driven by the readiness of the connection to the windowing
+    /// system, and works with any async executor. It never ends on its own, but
+    /// yields an error if the connection fails
+    /// 
+    /// [`Event::AboutToWait`] is never yielded, since the stream doesn't know when
+    /// the application is done handling events
+    /// 
+    /// The stream is [`Send`] if `T` is, so it can be polled from a task on a
+    /// multithreaded executor
+    /// 
+    /// Requires the `async` feature
+    #[cfg(feature = "async")]
+    pub fn events(&self) -> Events<'_, T> {
+        Events { event_loop: self }
+    }
+
+    /// Waits for the next event recieved by the event loop
+    /// 
+    /// This is equivalent to taking the next item from [`EventLoop::events()`]
+    /// 
+    /// Requires the `async` feature
+    #[cfg(feature = "async")]
+    pub async fn next_event(&self) -> Result<OwnedEvent<T>, Error> {
+        future::poll_fn(|cx| self.inner.poll_event(cx)).await
+    }
+}
+
+/// A stream of events recieved by an [`EventLoop`]
+/// 
+/// Created using [`EventLoop::events()`]
+#[cfg(feature = "async")]
+pub struct Events<'a, T> {
+    event_loop: &'a EventLoop<T>
+}
+
+#[cfg(feature = "async")]
+impl<'a, T> Stream for Events<'a, T> {
+    type Item = Result<OwnedEvent<T>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.event_loop.inner.poll_event(cx).map(Some)
+    }
+}
+
+/// Exposes the file descriptor of the connection to the X11 server
+#[cfg(unix)]
+impl<T> AsRawFd for EventLoop<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
+
+/// Exposes the file descriptor of the connection to the X11 server
+#[cfg(unix)]
+impl<T> AsFd for EventLoop<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+/// Controls how [`EventLoop::run()`] waits for new events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlFlow {
+    /// Wait until a new event is recieved
+    /// 
+    /// This is the default
+    #[default]
+    Wait,
+
+    /// Don't wait for new events, sending [`Event::AboutToWait`] continuously
+    /// 
+    /// This is useful for applications that render continuously, such as games
+    Poll,
+
+    /// Wait until a new event is recieved or the given deadline is reached
+    /// 
+    /// Once the deadline is reached, [`Event::AboutToWait`] is sent again. If the
+    /// deadline is not moved forward the event loop stops waiting, so this should
+    /// be updated every time, for example to the next animation frame
+    WaitUntil(Instant),
+
+    /// Exit from the event loop
+    Exit
+}
+
+/// The result of a call to [`EventLoop::pump_events()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PumpStatus {
+    /// No window was requested to close
+    Continue,
+
+    /// At least one window recieved a
+    /// [`WindowEvent::CloseRequested`](crate::event::WindowEvent::CloseRequested)
+    /// event while pumping events
+    CloseRequested
+}
+
+/// Handles the events of an [`EventLoop`]
+/// 
+/// This allows application state to be kept in a plain struct that the event loop
+/// mutates through [`EventLoop::run_app()`]. Every method gets a `control_flow`
+/// parameter that works the same as the one passed to [`EventLoop::run()`]
+/// 
+/// Only [`ApplicationHandler::window_event()`] has to be implemented, the other
+/// methods do nothing by default
+pub trait ApplicationHandler<T = ()> {
+    /// Called when a window recieves an event
+    fn window_event(&mut self, window_id: WindowId, event: WindowEvent, control_flow: &mut ControlFlow);
+
+    /// Called when a user event is sent through an [`EventLoopProxy`]
+    fn user_event(&mut self, event: T, control_flow: &mut ControlFlow) {
+        let _ = (event, control_flow);
+    }
+
+    /// Called when the event loop has processed all pending events and is about to
+    /// wait for new ones
+    /// 
+    /// See [`Event::AboutToWait`]
+    fn about_to_wait(&mut self, control_flow: &mut ControlFlow) {
+        let _ = control_flow;
+    }
+
+    /// Called when monitors are connected, disconnected or reconfigured
+    /// 
+    /// See [`Event::MonitorsChanged`]
+    fn monitors_changed(&mut self, control_flow: &mut ControlFlow) {
+        let _ = control_flow;
+    }
+
+    /// Called once when the event loop exits
+    fn exiting(&mut self) {}
+}
+
+/// A handle used to wake up an [`EventLoop`] from another thread
+/// 
+/// Created using [`EventLoop::create_proxy()`]. Proxies can be cloned and sent to
+/// other threads
+pub struct EventLoopProxy<T> {
+    inner: platform::EventLoopProxy<T>
+}
+
+impl<T> EventLoopProxy<T> {
+    /// Sends a user event to the event loop
+    /// 
+    /// This wakes up the event loop if its waiting for events, and the event
+    /// is delivered to it as [`Event::User`]. Returns
+    /// [`Error::EventLoopClosed`] if the event loop has been dropped
+    pub fn send_event(&self, event: T) -> Result<(), Error> {
+        self.inner.send_event(event)
+    }
+}
+
+impl<T> Clone for EventLoopProxy<T> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+#[cfg(feature = "async")]
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+    assert_send::<Events<'_, ()>>();
+};
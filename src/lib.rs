@@ -2,20 +2,25 @@
 
 mod platform;
 mod error;
+mod event_loop;
+mod monitor;
 
 pub mod dpi;
 pub mod event;
 
 use raw_window_handle::{
-    WindowHandle, DisplayHandle,
     HasWindowHandle, HasDisplayHandle,
     HandleError
 };
 
 pub use error::Error;
+pub use event_loop::{EventLoop, EventLoopProxy, ApplicationHandler, ControlFlow, PumpStatus};
+pub use monitor::{MonitorHandle, VideoMode};
 
-use dpi::{Size, LogicalSize, PhysicalSize};
-use event::Event;
+#[cfg(feature = "async")]
+pub use event_loop::Events;
+
+use dpi::{Position, Size, LogicalSize, PhysicalSize, Rect, ScaleFactorSource};
 
 /// Initial configuration of a window
 /// 
@@ -24,7 +29,9 @@ pub struct WindowConfig<'a> {
     pub(crate) title: &'a str,
     pub(crate) visible: bool,
     pub(crate) resizable: bool,
-    pub(crate) size: Size
+    pub(crate) size: Size,
+    pub(crate) scale_factor_override: Option<f32>,
+    pub(crate) manual_frame_sync: bool
 }
 
 impl<'a> WindowConfig<'a> {
@@ -34,7 +41,9 @@ impl<'a> WindowConfig<'a> {
             title: "swil window",
             visible: true,
             resizable: true,
-            size: Size::Logical(LogicalSize { width: 750.0, height: 500.0 })
+            size: Size::Logical(LogicalSize { width: 750.0, height: 500.0 }),
+            scale_factor_override: None,
+            manual_frame_sync: false
         }
     }
 
@@ -42,7 +51,7 @@ impl<'a> WindowConfig<'a> {
     /// 
     /// The default value is "swil window"
     /// 
-    /// This can later be changed with [`Window::set_title()`]
+    /// This can later be changed with [`WindowHandle::set_title()`]
     pub fn title(mut self, title: &'a str) -> Self {
         self.title = title;
         self
@@ -54,7 +63,7 @@ impl<'a> WindowConfig<'a> {
     /// 
     /// Its possible for the window to display garbage data when first created.
     /// To prevent that, set this to false and then make the window visible using
-    /// [`Window::set_visible()`] when you are ready to render actual contents
+    /// [`WindowHandle::set_visible()`] when you are ready to render actual contents
     pub fn visible(mut self, visible: bool) -> Self {
         self.visible = visible;
         self
@@ -64,7 +73,7 @@ impl<'a> WindowConfig<'a> {
     /// 
     /// The default is `true`
     /// 
-    /// This can later be changed with [`Window::set_resizable()`]
+    /// This can later be changed with [`WindowHandle::set_resizable()`]
     pub fn resizable(mut self, resizable: bool) -> Self {
         self.resizable = resizable;
         self
@@ -76,25 +85,201 @@ impl<'a> WindowConfig<'a> {
     /// 
     /// This represents the inner size of the window, excluding the size of decorations
     /// 
-    /// This can later be retrieved with [`Window::size()`]
+    /// This can later be retrieved with [`WindowHandle::size()`]
     pub fn size(mut self, size: Size) -> Self {
         self.size = size;
         self
     }
+
+    /// Sets a fixed scale factor for the window
+    /// 
+    /// By default the scale factor is detected from the desktop settings and the monitor
+    /// the window is on, which can be wrong on projectors and virtual machines. Windows
+    /// with an overridden scale factor never recieve
+    /// [`WindowEvent::ScaleFactorChanged`](event::WindowEvent::ScaleFactorChanged) events
+    /// 
+    /// Scale factors that aren't positive are ignored
+    pub fn scale_factor_override(mut self, scale_factor: f32) -> Self {
+        self.scale_factor_override = Some(scale_factor);
+        self
+    }
+
+    /// Sets whether the application reports when frames have been drawn
+    /// 
+    /// The default is `false`, where a frame is assumed to be drawn once the
+    /// [`WindowEvent::RedrawRequested`](event::WindowEvent::RedrawRequested) handler
+    /// returns. Applications that render on another thread should set this, and call
+    /// [`WindowHandle::frame_presented()`] once each frame has been drawn, so window
+    /// managers that synchronize resizes wait for the actual frame
+    pub fn manual_frame_sync(mut self, manual_frame_sync: bool) -> Self {
+        self.manual_frame_sync = manual_frame_sync;
+        self
+    }
+
+    /// Converts into an [`OwnedWindowConfig`] that owns its title
+    pub fn into_owned(self) -> OwnedWindowConfig {
+        OwnedWindowConfig {
+            title: self.title.to_owned(),
+            visible: self.visible,
+            resizable: self.resizable,
+            size: self.size,
+            scale_factor_override: self.scale_factor_override,
+            manual_frame_sync: self.manual_frame_sync
+        }
+    }
+}
+
+impl<'a> Default for WindowConfig<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`WindowConfig`] that owns its data
+/// 
+/// Owned configs can be stored, and with the `serde` feature saved and restored, for
+/// example to remember window geometry between runs. Use [`OwnedWindowConfig::as_config()`]
+/// to create a window from it. Fields missing when deserializing use the default values
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct OwnedWindowConfig {
+    pub title: String,
+    pub visible: bool,
+    pub resizable: bool,
+    pub size: Size,
+    pub scale_factor_override: Option<f32>,
+    pub manual_frame_sync: bool
+}
+
+impl OwnedWindowConfig {
+    /// Borrows as a [`WindowConfig`] that can be passed to [`Window::new()`]
+    pub fn as_config(&self) -> WindowConfig<'_> {
+        WindowConfig {
+            title: &self.title,
+            visible: self.visible,
+            resizable: self.resizable,
+            size: self.size,
+            scale_factor_override: self.scale_factor_override,
+            manual_frame_sync: self.manual_frame_sync
+        }
+    }
+}
+
+impl Default for OwnedWindowConfig {
+    fn default() -> Self {
+        WindowConfig::new().into_owned()
+    }
+}
+
+/// A unique identifier for a window
+/// 
+/// Events passed to [`EventLoop::run()`] are tagged with the ID of the window
+/// they were recieved by, which can be compared against [`WindowHandle::id()`] and
+/// [`ChildWindow::id()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowId(pub(crate) u64);
+
+/// The edge or corner of a window being resized
+/// 
+/// Used with [`WindowHandle::drag_resize_window()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeDirection {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest
+}
+
+/// The size of the decorations the window manager draws around a window
+/// 
+/// All values are in physical pixels, and are zero for undecorated windows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameExtents {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32
 }
 
 /// A graphical window
+/// 
+/// The window is destroyed when this is dropped. All of the window's methods are
+/// provided by [`WindowHandle`], which this dereferences to. Use [`Window::handle()`]
+/// to get a handle that can be sent to other threads
 pub struct Window {
-    inner: platform::Window
+    handle: WindowHandle
 }
 
 impl Window {
     /// Create a new window
     /// 
-    /// The given [`WindowConfig`] is used to set the initial configuration.
-    /// The options can be changed later using the window's setter methods
-    pub fn new(config: &WindowConfig) -> Result<Self, Error> {
-        platform::Window::new(config).map(|inner| Self { inner })
+    /// The window's events are delivered through the given [`EventLoop`]. The given
+    /// [`WindowConfig`] is used to set the initial configuration. The options can be
+    /// changed later using the window's setter methods
+    pub fn new<T>(event_loop: &EventLoop<T>, config: &WindowConfig) -> Result<Self, Error> {
+        platform::Window::new(&event_loop.inner, config).map(|inner| Self { handle: WindowHandle { inner } })
+    }
+
+    /// Gets a handle to the window
+    /// 
+    /// Handles can be cloned and sent to other threads, for example to call setters from
+    /// a worker thread or to render from a dedicated thread. Once the window is dropped,
+    /// methods on its handles return [`Error::WindowDestroyed`]
+    pub fn handle(&self) -> WindowHandle {
+        self.handle.clone()
+    }
+}
+
+impl std::ops::Deref for Window {
+    type Target = WindowHandle;
+
+    fn deref(&self) -> &WindowHandle {
+        &self.handle
+    }
+}
+
+impl Drop for Window {
+    fn drop(&mut self) {
+        self.handle.inner.destroy();
+    }
+}
+
+/// A handle to a [`Window`]
+/// 
+/// Handles are cheap to clone and can be shared between threads. They don't keep the
+/// window alive, it is destroyed when the [`Window`] is dropped
+#[derive(Clone)]
+pub struct WindowHandle {
+    inner: platform::Window
+}
+
+impl WindowHandle {
+    /// Gets the window's ID
+    pub fn id(&self) -> WindowId {
+        self.inner.id()
+    }
+
+    /// Creates a child window inside this window
+    /// 
+    /// The child window is positioned relative to the top left corner of this window.
+    /// It has its own window handle, which can be given to an external renderer to draw
+    /// into a region of this window. Logical positions and sizes are converted using this
+    /// window's scale factor
+    /// 
+    /// The given [`WindowConfig`] is used for the child's title and initial visibility,
+    /// its size and resizability options are ignored
+    /// 
+    /// Input events recieved by the child window are delivered through this window's
+    /// [`EventLoop`], tagged with the child's [`ChildWindow::id()`]
+    pub fn create_child(&self, config: &WindowConfig, position: Position, size: Size) -> Result<ChildWindow, Error> {
+        self.inner.create_child(config, &position, &size).map(|inner| ChildWindow { inner })
     }
 
     /// Sets the window's title
@@ -117,31 +302,289 @@ impl Window {
         self.inner.size()
     }
 
+    /// Sets the shape of the window
+    /// 
+    /// Only the parts of the window covered by the given rectangles are drawn, the rest
+    /// of the window is fully transparent. Passing `None` restores the default
+    /// rectangular shape
+    /// 
+    /// On X11, this requires the `SHAPE` extension
+    pub fn set_shape(&self, rects: Option<&[Rect]>) -> Result<(), Error> {
+        self.inner.set_shape(rects)
+    }
+
+    /// Sets the region of the window that recieves input
+    /// 
+    /// Mouse input outside the given rectangles passes through to whatever is below the
+    /// window. An empty slice makes the whole window click-through. Passing `None`
+    /// restores the default, where the whole window recieves input
+    /// 
+    /// On X11, this requires the `SHAPE` extension
+    pub fn set_input_region(&self, rects: Option<&[Rect]>) -> Result<(), Error> {
+        self.inner.set_input_region(rects)
+    }
+
+    /// Sets the opacity of the whole window
+    /// 
+    /// The value ranges from `0.0` (fully transparent) to `1.0` (fully opaque), values
+    /// outside that range are clamped and NaN is treated as opaque. This works with any
+    /// window, but requires a running compositor to have an effect
+    pub fn set_opacity(&self, opacity: f32) -> Result<(), Error> {
+        self.inner.set_opacity(opacity)
+    }
+
+    /// Sets the region behind the window that the compositor should blur
+    /// 
+    /// An empty slice blurs behind the whole window, and `None` disables blurring.
+    /// This is only a hint, and is ignored by compositors that don't support it
+    /// 
+    /// On X11, this sets the `_KDE_NET_WM_BLUR_BEHIND_REGION` property
+    pub fn set_blur_behind(&self, rects: Option<&[Rect]>) -> Result<(), Error> {
+        self.inner.set_blur_behind(rects)
+    }
+
+    /// Gets the monitor that most of the window is on
+    pub fn current_monitor(&self) -> Result<MonitorHandle, Error> {
+        self.inner.current_monitor().map(|inner| MonitorHandle { inner })
+    }
+
+    /// Reports that a frame has been drawn, for windows created with
+    /// [`WindowConfig::manual_frame_sync()`]
+    /// 
+    /// This can be called from any thread once the frame for a
+    /// [`WindowEvent::RedrawRequested`](event::WindowEvent::RedrawRequested) event has
+    /// been presented. Window managers that synchronize resizes wait for it before
+    /// showing the new window size
+    /// 
+    /// On X11, this answers any pending `_NET_WM_SYNC_REQUEST` and does nothing if the
+    /// `SYNC` extension is missing
+    pub fn frame_presented(&self) -> Result<(), Error> {
+        self.inner.frame_presented()
+    }
+
+    /// Gets the serial of the window manager's pending sync request, if any
+    /// 
+    /// The request is answered by the first frame finished after the resize it
+    /// announced has been recieved, see [`WindowHandle::frame_presented()`]
+    /// 
+    /// On X11, this is the serial of the latest `_NET_WM_SYNC_REQUEST`
+    pub fn sync_request_serial(&self) -> Option<u64> {
+        self.inner.sync_request_serial()
+    }
+
+    /// Requests a [`WindowEvent::FramePresented`](event::WindowEvent::FramePresented)
+    /// event at the next vertical blank
+    /// 
+    /// This can be used to pace rendering and animations with the display's refresh
+    /// rate. Requests are one-shot, so this should be called again for every frame
+    /// 
+    /// On X11, this requires the `Present` extension, which is missing on some servers
+    /// such as Xvfb. In that case [`Error::X11ExtensionMissing`] is returned and the
+    /// application should fall back to its own timing
+    pub fn request_frame_callback(&self) -> Result<(), Error> {
+        self.inner.request_frame_callback()
+    }
+
+    /// Starts an interactive move of the window, driven by the window manager
+    /// 
+    /// This is meant for applications that draw their own decorations, and should be
+    /// called in response to a [`MouseInput`](event::MouseInput) press on the custom
+    /// title bar. The window manager then moves the window with the pointer until the
+    /// button is released
+    pub fn drag_window(&self) -> Result<(), Error> {
+        self.inner.drag_window()
+    }
+
+    /// Starts an interactive resize of the window, driven by the window manager
+    /// 
+    /// Like [`WindowHandle::drag_window()`], this should be called in response to a mouse
+    /// button press, usually on a custom drawn window border. The given direction
+    /// selects the edge or corner being dragged
+    pub fn drag_resize_window(&self, direction: ResizeDirection) -> Result<(), Error> {
+        self.inner.drag_resize_window(direction)
+    }
+
+    /// Shows the window manager's window menu at the given position
+    /// 
+    /// The position is relative to the top left corner of the window. This is usually
+    /// called when the custom title bar is right clicked. Not all window managers
+    /// support this
+    pub fn show_window_menu(&self, position: Position) -> Result<(), Error> {
+        self.inner.show_window_menu(&position)
+    }
+
+    /// Gets the current outer size of the window
+    /// 
+    /// This is the inner size returned by [`WindowHandle::size()`] plus the size of the
+    /// window manager's decorations
+    pub fn outer_size(&self) -> Result<PhysicalSize, Error> {
+        self.inner.outer_size()
+    }
+
+    /// Gets the size of the decorations the window manager draws around the window
+    /// 
+    /// On X11 this is read from `_NET_FRAME_EXTENTS`, which is requested before the
+    /// window is first shown. Window managers that don't support it, or haven't set it
+    /// yet, report zero extents. Changes are reported with
+    /// [`WindowEvent::FrameExtentsChanged`](event::WindowEvent::FrameExtentsChanged)
+    pub fn frame_extents(&self) -> Result<FrameExtents, Error> {
+        self.inner.frame_extents()
+    }
+
     /// Gets the window's current scale factor
     pub fn scale_factor(&self) -> f32 {
         self.inner.scale_factor()
     }
 
-    /// Runs the window event loop
+    /// Gets where the window's scale factor came from
     /// 
-    /// This function blocks the thread while waiting for new window events to
-    /// be recieved. The given closure is called to process each event. The closure
-    /// also gets a `exit: &mut bool` parameter, which is initially `false` and can
-    /// be set to `true` by the closure to indicate that it wants to exit from the
-    /// event loop
-    pub fn event_loop(&self, func: impl Fn(Event, &mut bool)) -> Result<(), Error> {
-        self.inner.event_loop(func)
+    /// This is [`ScaleFactorSource::Override`] if the window was created with
+    /// [`WindowConfig::scale_factor_override()`], otherwise it is the source of the scale
+    /// factor of the monitor the window is on
+    pub fn scale_factor_source(&self) -> ScaleFactorSource {
+        self.inner.scale_factor_source()
+    }
+
+    /// Requests that the window be redrawn
+    /// 
+    /// A [`WindowEvent::RedrawRequested`](event::WindowEvent::RedrawRequested) event
+    /// covering the whole window is sent after the current batch of events has been
+    /// processed. Multiple requests are merged into a single event
+    /// 
+    /// When called from another thread, or when the event loop is driven by
+    /// [`EventLoop::dispatch_pending()`] or an async event stream, the event loop is woken
+    /// up to handle the request
+    pub fn request_redraw(&self) -> Result<(), Error> {
+        self.inner.request_redraw()
+    }
+
+    /// Gets whether the window has been destroyed
+    pub fn is_destroyed(&self) -> bool {
+        self.inner.is_destroyed()
     }
 }
 
 impl HasWindowHandle for Window {
-    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
-        Ok(self.inner.window_handle())
+    fn window_handle(&self) -> Result<raw_window_handle::WindowHandle<'_>, HandleError> {
+        self.handle.window_handle()
     }
 }
 
 impl HasDisplayHandle for Window {
-    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+    fn display_handle(&self) -> Result<raw_window_handle::DisplayHandle<'_>, HandleError> {
+        self.handle.display_handle()
+    }
+}
+
+impl HasWindowHandle for WindowHandle {
+    fn window_handle(&self) -> Result<raw_window_handle::WindowHandle<'_>, HandleError> {
+        match self.inner.is_destroyed() {
+            true => Err(HandleError::Unavailable),
+            false => Ok(self.inner.window_handle())
+        }
+    }
+}
+
+impl HasDisplayHandle for WindowHandle {
+    fn display_handle(&self) -> Result<raw_window_handle::DisplayHandle<'_>, HandleError> {
         Ok(self.inner.display_handle())
     }
-}
\ No newline at end of file
+}
+
+/// A child window embedded inside a [`Window`]
+/// 
+/// Created using [`WindowHandle::create_child()`]. The child window is destroyed when
+/// this is dropped, or along with its parent
+/// 
+/// Like [`WindowHandle`], child windows can be sent to other threads
+pub struct ChildWindow {
+    inner: platform::ChildWindow
+}
+
+impl ChildWindow {
+    /// Gets the child window's ID
+    pub fn id(&self) -> WindowId {
+        self.inner.id()
+    }
+
+    /// Sets whether the child window is visible
+    pub fn set_visible(&self, visible: bool) -> Result<(), Error> {
+        self.inner.set_visible(visible)
+    }
+
+    /// Sets the child window's position, relative to the top left corner of its parent
+    pub fn set_position(&self, position: Position) -> Result<(), Error> {
+        self.inner.set_position(&position)
+    }
+
+    /// Sets the child window's size
+    pub fn set_size(&self, size: Size) -> Result<(), Error> {
+        self.inner.set_size(&size)
+    }
+
+    /// Gets the current size of the child window
+    pub fn size(&self) -> PhysicalSize {
+        self.inner.size()
+    }
+
+    /// Raises the child window above its siblings
+    pub fn raise(&self) -> Result<(), Error> {
+        self.inner.raise()
+    }
+
+    /// Lowers the child window below its siblings
+    pub fn lower(&self) -> Result<(), Error> {
+        self.inner.lower()
+    }
+}
+
+impl HasWindowHandle for ChildWindow {
+    fn window_handle(&self) -> Result<raw_window_handle::WindowHandle<'_>, HandleError> {
+        match self.inner.is_destroyed() {
+            true => Err(HandleError::Unavailable),
+            false => Ok(self.inner.window_handle())
+        }
+    }
+}
+
+impl HasDisplayHandle for ChildWindow {
+    fn display_handle(&self) -> Result<raw_window_handle::DisplayHandle<'_>, HandleError> {
+        Ok(self.inner.display_handle())
+    }
+}
+
+// Make sure window handles and child windows can be used from other threads
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<WindowHandle>();
+    assert_send_sync::<ChildWindow>();
+};
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use dpi::PhysicalSize;
+
+    #[test]
+    fn window_config_round_trip() {
+        let config = WindowConfig::new()
+            .title("saved window")
+            .visible(false)
+            .resizable(false)
+            .size(Size::Physical(PhysicalSize::new(1024, 768)))
+            .into_owned();
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(json, r#"{"title":"saved window","visible":false,"resizable":false,"size":{"Physical":{"width":1024,"height":768}},"scale_factor_override":null,"manual_frame_sync":false}"#);
+        assert_eq!(serde_json::from_str::<OwnedWindowConfig>(&json).unwrap(), config);
+    }
+
+    #[test]
+    fn window_config_missing_fields() {
+        let config = serde_json::from_str::<OwnedWindowConfig>(r#"{"title":"saved window"}"#).unwrap();
+        assert_eq!(config, WindowConfig::new().title("saved window").into_owned());
+
+        let config = serde_json::from_str::<OwnedWindowConfig>("{}").unwrap();
+        assert_eq!(config, OwnedWindowConfig::default());
+    }
+}
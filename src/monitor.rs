@@ -0,0 +1,122 @@
+use crate::{platform, dpi::{PhysicalPosition, PhysicalSize, Rect, ScaleFactorSource}};
+
+/// A monitor connected to the system
+/// 
+/// Monitors are listed with [`EventLoop::available_monitors()`](crate::EventLoop::available_monitors).
+/// A handle is a snapshot of the monitor's configuration when it was retrieved
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorHandle {
+    pub(crate) inner: platform::MonitorHandle
+}
+
+impl MonitorHandle {
+    /// Gets the monitor's name
+    /// 
+    /// On X11 this is the RandR monitor name, such as `HDMI-1`
+    pub fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    /// Gets the position of the monitor's top left corner on the desktop
+    pub fn position(&self) -> PhysicalPosition {
+        self.inner.position()
+    }
+
+    /// Gets the monitor's resolution
+    pub fn size(&self) -> PhysicalSize {
+        self.inner.size()
+    }
+
+    /// Gets the part of the monitor not covered by panels and docks
+    /// 
+    /// This is where windows should be placed to stay fully visible. On X11 this is
+    /// read from `_GTK_WORKAREAS_Dn` or `_NET_WORKAREA` for the current desktop,
+    /// falling back to the whole monitor
+    /// 
+    /// Like [`MonitorHandle::position()`], the position is in desktop coordinates
+    /// rather than relative to the monitor
+    pub fn work_area(&self) -> Rect {
+        self.inner.work_area()
+    }
+
+    /// Gets the monitor's physical width and height in millimetres
+    /// 
+    /// This is `(0, 0)` for monitors that don't report a physical size, such as some
+    /// projectors
+    pub fn physical_size_mm(&self) -> (u32, u32) {
+        self.inner.physical_size_mm()
+    }
+
+    /// Gets the monitor's refresh rate in millihertz, if known
+    pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+        self.inner.refresh_rate_millihertz()
+    }
+
+    /// Gets the scale factor of windows on this monitor
+    pub fn scale_factor(&self) -> f32 {
+        self.inner.scale_factor()
+    }
+
+    /// Gets where the monitor's scale factor came from
+    pub fn scale_factor_source(&self) -> ScaleFactorSource {
+        self.inner.scale_factor_source()
+    }
+
+    /// Gets whether this is the primary monitor
+    pub fn is_primary(&self) -> bool {
+        self.inner.is_primary()
+    }
+
+    /// Gets all video modes supported by the monitor
+    /// 
+    /// On X11 these are the RandR modes of the monitor's output, with the preferred modes
+    /// first. This is empty if the modes couldn't be queried, such as when RandR isn't
+    /// available
+    pub fn video_modes(&self) -> Vec<VideoMode> {
+        self.inner
+            .video_modes()
+            .iter()
+            .map(|inner| VideoMode { inner: inner.clone() })
+            .collect()
+    }
+
+    /// Gets the video mode the monitor is currently using, if known
+    pub fn current_video_mode(&self) -> Option<VideoMode> {
+        self.inner.current_video_mode().map(|inner| VideoMode { inner: inner.clone() })
+    }
+}
+
+/// A display mode supported by a monitor
+/// 
+/// Retrieved using [`MonitorHandle::video_modes()`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoMode {
+    pub(crate) inner: platform::VideoMode
+}
+
+impl VideoMode {
+    /// Gets the resolution of the mode
+    pub fn size(&self) -> PhysicalSize {
+        self.inner.size()
+    }
+
+    /// Gets the number of bits per pixel
+    pub fn bit_depth(&self) -> u16 {
+        self.inner.bit_depth()
+    }
+
+    /// Gets the refresh rate of the mode in millihertz
+    /// 
+    /// This is `0` if the refresh rate is unknown
+    pub fn refresh_rate_millihertz(&self) -> u32 {
+        self.inner.refresh_rate_millihertz()
+    }
+
+    /// Gets the RandR mode ID of this mode
+    /// 
+    /// This can be used with RandR requests such as `SetCrtcConfig` to switch modes
+    #[cfg(unix)]
+    pub fn randr_mode(&self) -> u32 {
+        self.inner.mode()
+    }
+}
@@ -2,4 +2,4 @@
 mod xcb;
 
 #[cfg(unix)]
-pub use xcb::Window;
\ No newline at end of file
+pub use xcb::{EventLoop, EventLoopProxy, Window, ChildWindow, MonitorHandle, VideoMode};
\ No newline at end of file
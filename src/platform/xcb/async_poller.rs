@@ -0,0 +1,135 @@
+use std::io;
+use std::thread::{self, JoinHandle};
+use std::os::fd::RawFd;
+use std::sync::{Arc, Mutex, Condvar};
+use std::task::Waker;
+
+use crate::Error;
+
+/// Wakes a task when the X11 connection becomes readable
+/// 
+/// This works with any executor by waiting for the connection on a background thread.
+/// The thread only polls the connection while armed, since the connection stays
+/// readable until the event loop reads from it
+pub struct AsyncPoller {
+    state: Arc<(Mutex<PollerState>, Condvar)>,
+    shutdown_fd: RawFd,
+    thread: Option<JoinHandle<()>>
+}
+
+struct PollerState {
+    waker: Option<Waker>,
+    armed: bool,
+    shutdown: bool
+}
+
+impl AsyncPoller {
+    pub fn new(conn_fd: RawFd) -> Result<Self, Error> {
+        // Pipe used to interrupt the thread while its waiting on the connection. It isn't
+        // inherited by child processes
+        let mut pipe_fds = [0; 2];
+
+        if unsafe { libc::pipe2(pipe_fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+            return Err(Error::PollFailed(io::Error::last_os_error()));
+        }
+
+        let [shutdown_read_fd, shutdown_fd] = pipe_fds;
+
+        let state = Arc::new((
+            Mutex::new(PollerState { waker: None, armed: false, shutdown: false }),
+            Condvar::new()
+        ));
+
+        let thread_state = state.clone();
+        let thread = thread::spawn(move || poll_thread(&thread_state, conn_fd, shutdown_read_fd));
+
+        Ok(Self {
+            state,
+            shutdown_fd,
+            thread: Some(thread)
+        })
+    }
+
+    /// Wakes the given waker the next time the connection becomes readable
+    pub fn arm(&self, waker: &Waker) {
+        let (lock, condvar) = &*self.state;
+        let mut state = lock.lock().unwrap();
+
+        match &mut state.waker {
+            Some(current) => current.clone_from(waker),
+            None => state.waker = Some(waker.clone())
+        }
+
+        state.armed = true;
+        condvar.notify_one();
+    }
+}
+
+impl Drop for AsyncPoller {
+    fn drop(&mut self) {
+        let (lock, condvar) = &*self.state;
+        lock.lock().unwrap().shutdown = true;
+        condvar.notify_one();
+
+        unsafe {
+            libc::write(self.shutdown_fd, [0u8].as_ptr().cast(), 1);
+        }
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+
+        unsafe {
+            libc::close(self.shutdown_fd);
+        }
+    }
+}
+
+fn poll_thread(state: &(Mutex<PollerState>, Condvar), conn_fd: RawFd, shutdown_fd: RawFd) {
+    let (lock, condvar) = state;
+
+    loop {
+        // Wait until a task wants to be woken
+        {
+            let mut state = lock.lock().unwrap();
+
+            while !state.armed && !state.shutdown {
+                state = condvar.wait(state).unwrap();
+            }
+
+            if state.shutdown {
+                break;
+            }
+        }
+
+        let mut poll_fds = [
+            libc::pollfd { fd: conn_fd, events: libc::POLLIN, revents: 0 },
+            libc::pollfd { fd: shutdown_fd, events: libc::POLLIN, revents: 0 }
+        ];
+
+        let res = unsafe { libc::poll(poll_fds.as_mut_ptr(), 2, -1) };
+
+        if res < 0 && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+            continue;
+        }
+
+        if poll_fds[1].revents != 0 {
+            break;
+        }
+
+        // Also wake on errors, so the task can find out about them from the connection
+        let waker = {
+            let mut state = lock.lock().unwrap();
+            state.armed = false;
+            state.waker.take()
+        };
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    unsafe {
+        libc::close(shutdown_fd);
+    }
+}
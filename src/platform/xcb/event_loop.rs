@@ -0,0 +1,1332 @@
+use std::collections::HashMap;
+use std::io;
+use std::mem;
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+use std::os::fd::{AsRawFd, AsFd, RawFd, BorrowedFd};
+use std::sync::{Arc, Mutex, atomic::AtomicBool, mpsc::{self, Sender, Receiver}};
+
+#[cfg(feature = "async")]
+use std::{collections::VecDeque, task::{Context, Poll}};
+
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    xcb_ffi::{self, XCBConnection},
+    protocol::{
+        Event as X11Event,
+        sync::{self, ConnectionExt as _, Int64},
+        present::{self, ConnectionExt as _},
+        randr::{self, ConnectionExt as _},
+        xinerama,
+        xproto::{
+            ConnectionExt as _,
+            WindowClass,
+            CreateWindowAux,
+            ChangeWindowAttributesAux,
+            ConfigureWindowAux,
+            AtomEnum,
+            ClientMessageEvent,
+            EventMask
+        }
+    }
+};
+
+use crate::{
+    WindowId,
+    FrameExtents,
+    ControlFlow,
+    PumpStatus,
+    Error,
+    dpi::{PhysicalSize, PhysicalPosition, Rect, ScaleFactorSource},
+    event::{Event, OwnedEvent, WindowEvent, KeyboardInput, MouseInput, MouseButton, ButtonState, MouseScroll, SuggestedSize}
+};
+
+use super::{AtomSet, MonitorHandle, ScaleSettings, monitor::{self, MonitorSources}, settings::{self, EnvScaleFactors}};
+
+#[cfg(feature = "async")]
+use super::async_poller::AsyncPoller;
+
+/// State shared between the event loop and all windows created from it
+pub struct Shared {
+    pub conn: XCBConnection,
+    pub screen_num: usize,
+    pub root: u32,
+
+    /// Hidden window used to wake up the event loop
+    pub proxy_window: u32,
+
+    /// Thread running `run()` or `pump_events()`, which check for redraws before waiting
+    /// 
+    /// The event loop can be moved to another thread after it's created, so this is
+    /// updated whenever events are dispatched. It's `None` when the event loop is
+    /// driven by a reactor or an async executor, which only wake up when the connection
+    /// becomes readable
+    pub loop_thread: Mutex<Option<ThreadId>>,
+
+    pub atoms: AtomSet,
+    pub sync_supported: bool,
+    pub present_supported: bool,
+
+    /// Monitors known to the server, used to calculate window scale factors. These are
+    /// queried again when RandR reports a change
+    pub monitors: Mutex<Vec<MonitorHandle>>,
+    pub monitor_sources: MonitorSources,
+
+    /// The `_GTK_WORKAREAS_Dn` atom for the current desktop, whose changes update the
+    /// work areas of the monitors
+    pub gtk_work_areas_atom: Mutex<u32>,
+
+    /// Scale factors set with `SWIL_SCALE_FACTOR`, which override everything else
+    pub env_scale_factors: EnvScaleFactors,
+
+    /// Desktop settings that override the monitor scale factors
+    pub scale_settings: Mutex<ScaleSettings>,
+
+    /// The `_XSETTINGS_Sn` selection for this screen, and the window currently owning it
+    pub xsettings_selection: u32,
+    pub xsettings_owner: Mutex<Option<u32>>,
+
+    pub last_button_press: Mutex<Option<(i16, i16, u8)>>,
+    pub windows: Mutex<HashMap<u32, Arc<WindowState>>>
+}
+
+impl Shared {
+    /// Wakes up the event loop if its waiting for events
+    pub fn wake_up(&self) -> Result<(), Error> {
+        // With an empty event mask the message is delivered to the client that created
+        // the window, which is the event loop
+        let message = ClientMessageEvent::new(32, self.proxy_window, self.atoms._SWIL_WAKEUP, [0; 5]);
+
+        self.conn
+            .send_event(false, self.proxy_window, EventMask::NO_EVENT, message)
+            .map_err(Error::X11SendEventFailed)?;
+
+        self.conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    /// Wakes up the event loop after a round trip, unless it was made from inside `run()`
+    /// or `pump_events()`
+    /// 
+    /// Round trips read any events that arrive in the meantime into libxcb's queue, where
+    /// an event loop waiting on the connection doesn't see them
+    pub fn wake_after_round_trip(&self) -> Result<(), Error> {
+        if *self.loop_thread.lock().unwrap() != Some(thread::current().id()) {
+            self.wake_up()?;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the monitor that contains most of the given rectangle, falling back to the
+    /// primary monitor
+    pub fn monitor_at(&self, x: i32, y: i32, width: u32, height: u32) -> Option<MonitorHandle> {
+        let monitors = self.monitors.lock().unwrap();
+
+        monitors
+            .iter()
+            .filter(|monitor| monitor.overlap(x, y, width, height) > 0)
+            .max_by_key(|monitor| monitor.overlap(x, y, width, height))
+            .or_else(|| monitors.iter().find(|monitor| monitor.is_primary()))
+            .or_else(|| monitors.first())
+            .cloned()
+    }
+
+    /// Gets the primary monitor, or the first monitor if none is marked as primary
+    pub fn primary_monitor(&self) -> Option<MonitorHandle> {
+        let monitors = self.monitors.lock().unwrap();
+
+        monitors
+            .iter()
+            .find(|monitor| monitor.is_primary())
+            .or_else(|| monitors.first())
+            .cloned()
+    }
+
+    /// Gets the scale factor of the primary monitor and where it came from, used for
+    /// new windows
+    pub fn primary_scale_factor(&self) -> (f32, ScaleFactorSource) {
+        self.primary_monitor()
+            .map(|monitor| (monitor.scale_factor(), monitor.scale_factor_source()))
+            .unwrap_or((1.0, ScaleFactorSource::Default))
+    }
+
+    /// Whether all monitors have the same scale factor, in which case windows never
+    /// need to check which monitor they're on
+    fn uniform_scale_factor(&self) -> bool {
+        let monitors = self.monitors.lock().unwrap();
+
+        monitors.windows(2).all(|pair| pair[0].scale_factor() == pair[1].scale_factor())
+    }
+
+    fn window_state(&self, window: u32) -> Option<Arc<WindowState>> {
+        self.windows.lock().unwrap().get(&window).cloned()
+    }
+}
+
+/// Per window state that is kept updated by the event loop
+/// 
+/// This can be accessed from any thread through window handles
+pub struct WindowState {
+    pub size: Mutex<PhysicalSize>,
+
+    /// Scale factor of the monitor the window is on and where it came from, unless it
+    /// was overridden when creating the window
+    pub scale_factor: Mutex<(f32, ScaleFactorSource)>,
+
+    /// Size of the window manager's decorations, from `_NET_FRAME_EXTENTS`
+    pub frame_extents: Mutex<FrameExtents>,
+
+    /// Regions that need to be redrawn, a redraw is pending if this isn't empty
+    pub damage: Mutex<Vec<Rect>>,
+
+    /// `_NET_WM_SYNC_REQUEST` state, if the sync extension is available
+    pub sync: Option<SyncState>,
+
+    /// Present extension event ID, set once frame callbacks have been requested
+    pub present_event: Mutex<Option<u32>>,
+
+    /// MSC and UST of the last presented frame
+    pub last_frame: Mutex<Option<(u64, u64)>>,
+
+    /// Set once the window has been destroyed, after which handles stop working
+    pub destroyed: AtomicBool
+}
+
+impl WindowState {
+    pub fn size(&self) -> PhysicalSize {
+        *self.size.lock().unwrap()
+    }
+
+    /// Adds a region that needs to be redrawn
+    /// 
+    /// Regions covering the whole window replace all other damage, so repeated redraw
+    /// requests are merged into a single region. Returns whether a redraw was already
+    /// pending
+    pub fn add_damage(&self, rect: Rect) -> bool {
+        let size = self.size();
+        let covers_window = |rect: &Rect| {
+            rect.position.x <= 0 &&
+            rect.position.y <= 0 &&
+            rect.position.x as i64 + rect.size.width as i64 >= size.width as i64 &&
+            rect.position.y as i64 + rect.size.height as i64 >= size.height as i64
+        };
+
+        let mut damage = self.damage.lock().unwrap();
+        let pending = !damage.is_empty();
+
+        if damage.iter().any(covers_window) {
+            return pending;
+        }
+
+        if covers_window(&rect) {
+            damage.clear();
+        }
+
+        damage.push(rect);
+
+        pending
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor.lock().unwrap().0
+    }
+
+    pub fn scale_factor_source(&self) -> ScaleFactorSource {
+        self.scale_factor.lock().unwrap().1
+    }
+
+    fn redraw_pending(&self) -> bool {
+        !self.damage.lock().unwrap().is_empty()
+    }
+}
+
+pub struct SyncState {
+    pub basic_counter: u32,
+    pub extended_counter: u32,
+
+    /// The latest sync request recieved from the window manager
+    pub request: Mutex<Option<SyncRequest>>,
+
+    /// The current value of the extended counter. Locked while the counter is being
+    /// updated, since frames can be finished from other threads
+    pub frame_counter: Mutex<u64>,
+
+    /// Whether frames are finished by the application calling `frame_presented()`,
+    /// rather than once the redraw event has been handled
+    pub manual: bool
+}
+
+/// A `_NET_WM_SYNC_REQUEST` recieved from the window manager
+#[derive(Debug, Clone, Copy)]
+pub struct SyncRequest {
+    pub serial: u64,
+
+    /// Whether the request is for the extended counter
+    pub extended: bool,
+
+    /// Whether the configure event following the request has been recieved. The request
+    /// is only answered by frames drawn after it, at the new size
+    pub configured: bool
+}
+
+impl SyncState {
+    /// Marks the start of a frame by making the extended sync counter odd
+    pub fn start_frame(&self, conn: &impl Connection) -> Result<(), Error> {
+        let mut frame_counter = self.frame_counter.lock().unwrap();
+
+        if frame_counter.is_multiple_of(2) {
+            *frame_counter += 1;
+
+            conn
+                .sync_set_counter(self.extended_counter, to_int64(*frame_counter))
+                .map_err(Error::X11SyncCounterFailed)?;
+        }
+
+        Ok(())
+    }
+
+    /// Marks the end of a frame, answering the pending sync request if its configure
+    /// event has been recieved
+    pub fn finish_frame(&self, conn: &impl Connection) -> Result<(), Error> {
+        let mut frame_counter = self.frame_counter.lock().unwrap();
+
+        let request = {
+            let mut request = self.request.lock().unwrap();
+
+            match *request {
+                Some(SyncRequest { configured: true, .. }) => request.take(),
+                _ => None
+            }
+        };
+
+        // The extended counter must be even when no frame is being drawn, and must reach
+        // at least the value requested by the window manager
+        let mut value = *frame_counter + 1;
+
+        match request {
+            Some(SyncRequest { serial, extended: true, .. }) => value = value.max(serial),
+
+            Some(SyncRequest { serial, extended: false, .. }) => {
+                conn
+                    .sync_set_counter(self.basic_counter, to_int64(serial))
+                    .map_err(Error::X11SyncCounterFailed)?;
+            },
+
+            None => ()
+        }
+
+        if !value.is_multiple_of(2) {
+            value += 1;
+        }
+
+        *frame_counter = value;
+
+        conn
+            .sync_set_counter(self.extended_counter, to_int64(value))
+            .map_err(Error::X11SyncCounterFailed)?;
+
+        conn.flush().map_err(Error::X11FlushFailed)
+    }
+}
+
+pub struct EventLoop<T> {
+    shared: Arc<Shared>,
+    user_event_sender: Sender<T>,
+    user_event_receiver: Mutex<Receiver<T>>,
+
+    /// Sizes suggested by scale factor change events, applied once the event is handled
+    suggested_sizes: Mutex<Vec<(u32, SuggestedSize)>>,
+
+    /// Events recieved but not yet returned by `poll_event()`
+    #[cfg(feature = "async")]
+    event_queue: Mutex<VecDeque<OwnedEvent<T>>>,
+
+    /// Windows whose redraw event was returned by `poll_event()`. Their frame is finished
+    /// on the next call, once the event has been handled
+    #[cfg(feature = "async")]
+    yielded_redraws: Mutex<Vec<u32>>,
+
+    /// Created the first time `poll_event()` has to wait
+    #[cfg(feature = "async")]
+    poller: Mutex<Option<AsyncPoller>>
+}
+
+impl<T> EventLoop<T> {
+    pub fn new() -> Result<Self, Error> {
+        // Load libxcb
+        xcb_ffi::load_libxcb().map_err(Error::XcbLoadFailed)?;
+
+        // Connect to X11 server
+        let (conn, screen_num) = XCBConnection::connect(None).map_err(Error::X11ConnectionFailed)?;
+        let screen = &conn.setup().roots[screen_num];
+        let root = screen.root;
+
+        // Get needed atoms
+        let atoms = AtomSet::new(&conn)
+            .map_err(Error::X11AtomFetchFailed)?
+            .reply()
+            .map_err(Error::X11AtomReplyError)?;
+
+        // Initialize the sync extension, needed for synchronized resizing
+        let sync_ext = conn
+            .extension_information(sync::X11_EXTENSION_NAME)
+            .map_err(Error::X11ExtensionQueryFailed)?;
+
+        let sync_supported = match sync_ext {
+            Some(_) => conn
+                .sync_initialize(3, 1)
+                .map_err(Error::X11ExtensionQueryFailed)?
+                .reply()
+                .is_ok(),
+
+            None => false
+        };
+
+        // Check for the present extension, needed for frame timing
+        let present_ext = conn
+            .extension_information(present::X11_EXTENSION_NAME)
+            .map_err(Error::X11ExtensionQueryFailed)?;
+
+        let present_supported = match present_ext {
+            Some(_) => conn
+                .present_query_version(1, 0)
+                .map_err(Error::X11ExtensionQueryFailed)?
+                .reply()
+                .is_ok(),
+
+            None => false
+        };
+
+        // Check for the RandR and Xinerama extensions, needed to query monitors
+        let randr_ext = conn
+            .extension_information(randr::X11_EXTENSION_NAME)
+            .map_err(Error::X11ExtensionQueryFailed)?;
+
+        let randr_version = match randr_ext {
+            Some(_) => conn
+                .randr_query_version(1, 5)
+                .map_err(Error::X11ExtensionQueryFailed)?
+                .reply()
+                .ok()
+                .map(|version| (version.major_version, version.minor_version)),
+
+            None => None
+        };
+
+        // Version 1.2 is needed for output change events, and 1.5 for GetMonitors
+        if randr_version >= Some((1, 2)) {
+            let mask = randr::NotifyMask::SCREEN_CHANGE | randr::NotifyMask::CRTC_CHANGE | randr::NotifyMask::OUTPUT_CHANGE;
+            conn.randr_select_input(root, mask).map_err(Error::X11SelectInputFailed)?;
+        }
+
+        let xinerama_supported = conn
+            .extension_information(xinerama::X11_EXTENSION_NAME)
+            .map_err(Error::X11ExtensionQueryFailed)?
+            .is_some();
+
+        let monitor_sources = MonitorSources {
+            randr: randr_version >= Some((1, 5)),
+            xinerama: xinerama_supported
+        };
+
+        // Watch the root window for resource database changes and for new XSETTINGS
+        // managers, which announce themselves with a MANAGER message
+        let aux = ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE | EventMask::STRUCTURE_NOTIFY);
+        conn.change_window_attributes(root, &aux).map_err(Error::X11SelectInputFailed)?;
+
+        let xsettings_selection = conn
+            .intern_atom(false, format!("_XSETTINGS_S{screen_num}").as_bytes())
+            .map_err(Error::X11AtomFetchFailed)?
+            .reply()
+            .map_err(Error::X11AtomReplyError)?
+            .atom;
+
+        // Get scale factor
+        // Xft.dpi and XSETTINGS override the scale factor calculated from monitor sizes
+        let xsettings_owner = settings::watch_xsettings_owner(&conn, xsettings_selection);
+
+        let (xsettings_dpi, window_scaling_factor) = match xsettings_owner {
+            Some(owner) => settings::read_xsettings(&conn, owner, atoms._XSETTINGS_SETTINGS),
+            None => (None, None)
+        };
+
+        let scale_settings = ScaleSettings {
+            resource_dpi: settings::read_resource_dpi(&conn),
+            xsettings_dpi,
+            window_scaling_factor
+        };
+
+        let env_scale_factors = EnvScaleFactors::from_env();
+        let monitors = monitor::query_monitors_or_fallback(&conn, screen, &atoms, monitor_sources, &env_scale_factors, scale_settings.dpi());
+
+        // The atom is created so the property can be recognized once it's first set
+        let desktop = monitor::current_desktop(&conn, root, &atoms);
+        let gtk_work_areas_atom = monitor::gtk_work_areas_atom(&conn, desktop, false);
+
+        // Create a hidden window that proxies send wakeup messages to
+        let proxy_window = conn.generate_id().map_err(Error::X11GenerateIdFailed)?;
+
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            proxy_window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            0,
+            &CreateWindowAux::new()
+        ).map_err(Error::X11CreateWindowFailed)?;
+
+        conn.flush().map_err(Error::X11FlushFailed)?;
+
+        let (user_event_sender, user_event_receiver) = mpsc::channel();
+
+        let shared = Shared {
+            conn,
+            screen_num,
+            root,
+            proxy_window,
+            loop_thread: Mutex::new(None),
+            atoms,
+            sync_supported,
+            present_supported,
+            monitors: Mutex::new(monitors),
+            monitor_sources,
+            gtk_work_areas_atom: Mutex::new(gtk_work_areas_atom),
+            env_scale_factors,
+            scale_settings: Mutex::new(scale_settings),
+            xsettings_selection,
+            xsettings_owner: Mutex::new(xsettings_owner),
+            last_button_press: Mutex::new(None),
+            windows: Mutex::new(HashMap::new())
+        };
+
+        Ok(Self {
+            shared: Arc::new(shared),
+            user_event_sender,
+            user_event_receiver: Mutex::new(user_event_receiver),
+            suggested_sizes: Mutex::new(Vec::new()),
+
+            #[cfg(feature = "async")]
+            event_queue: Mutex::new(VecDeque::new()),
+
+            #[cfg(feature = "async")]
+            yielded_redraws: Mutex::new(Vec::new()),
+
+            #[cfg(feature = "async")]
+            poller: Mutex::new(None)
+        })
+    }
+
+    pub(super) fn shared(&self) -> &Arc<Shared> {
+        &self.shared
+    }
+
+    pub fn available_monitors(&self) -> Vec<MonitorHandle> {
+        self.shared.monitors.lock().unwrap().clone()
+    }
+
+    pub fn primary_monitor(&self) -> Option<MonitorHandle> {
+        self.shared.primary_monitor()
+    }
+
+    pub fn create_proxy(&self) -> EventLoopProxy<T> {
+        EventLoopProxy {
+            shared: self.shared.clone(),
+            sender: self.user_event_sender.clone()
+        }
+    }
+
+    pub fn run(&self, mut func: impl FnMut(Event<T>, &mut ControlFlow)) -> Result<(), Error> {
+        self.set_loop_thread(true);
+
+        let mut control_flow = ControlFlow::Wait;
+
+        loop {
+            self.dispatch_queued(&mut |event| {
+                if control_flow != ControlFlow::Exit {
+                    func(event, &mut control_flow);
+                }
+            })?;
+
+            self.apply_suggested_sizes()?;
+
+            if control_flow == ControlFlow::Exit {
+                return Ok(());
+            }
+
+            func(Event::AboutToWait, &mut control_flow);
+
+            self.dispatch_redraws(true, &mut |event| {
+                if control_flow != ControlFlow::Exit {
+                    func(event, &mut control_flow);
+                }
+            })?;
+
+            // Don't wait if a redraw was requested while handling the previous redraws
+            if self.redraw_pending() && control_flow != ControlFlow::Exit {
+                continue;
+            }
+
+            // Round trips made by the handler read events into libxcb's queue, where waiting
+            // on the connection doesn't see them, so dispatch those before waiting
+            if control_flow != ControlFlow::Exit {
+                let recieved = self.dispatch_queued(&mut |event| {
+                    if control_flow != ControlFlow::Exit {
+                        func(event, &mut control_flow);
+                    }
+                })?;
+
+                if recieved {
+                    continue;
+                }
+            }
+
+            match control_flow {
+                ControlFlow::Wait => self.wait_readable(None)?,
+                ControlFlow::Poll => (),
+
+                ControlFlow::WaitUntil(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    self.wait_readable(Some(timeout))?;
+                },
+
+                ControlFlow::Exit => return Ok(())
+            }
+        }
+    }
+
+    pub fn pump_events(&self, timeout: Option<Duration>, mut func: impl FnMut(Event<T>)) -> Result<PumpStatus, Error> {
+        self.set_loop_thread(true);
+
+        let mut close_requested = false;
+
+        let mut func = |event: Event<T>| {
+            if let Event::WindowEvent { event: WindowEvent::CloseRequested, .. } = &event {
+                close_requested = true;
+            }
+
+            func(event);
+        };
+
+        // Only wait if there weren't any events or redraws already pending
+        if !self.dispatch_queued(&mut func)? && !self.redraw_pending() && timeout != Some(Duration::ZERO) {
+            self.wait_readable(timeout)?;
+            self.dispatch_queued(&mut func)?;
+        }
+
+        self.apply_suggested_sizes()?;
+
+        self.dispatch_redraws(true, &mut func)?;
+
+        if close_requested {
+            Ok(PumpStatus::CloseRequested)
+        }
+        else {
+            Ok(PumpStatus::Continue)
+        }
+    }
+
+    pub fn dispatch_pending(&self, mut func: impl FnMut(Event<T>)) -> Result<(), Error> {
+        self.set_loop_thread(false);
+        self.dispatch_queued(&mut func)?;
+        self.apply_suggested_sizes()?;
+        self.dispatch_redraws(true, &mut func)?;
+
+        // Requests made while handling events must be sent before the caller goes
+        // back to waiting on the connection
+        self.shared.conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    #[cfg(feature = "async")]
+    pub fn poll_event(&self, cx: &mut Context<'_>) -> Poll<Result<OwnedEvent<T>, Error>> {
+        self.set_loop_thread(false);
+
+        // The previously returned redraw event has been handled by now
+        if let Err(err) = self.finish_yielded_redraws() {
+            return Poll::Ready(Err(err));
+        }
+
+        let event = self.event_queue.lock().unwrap().pop_front();
+
+        if let Some(event) = event {
+            return Poll::Ready(Ok(self.yield_event(event)));
+        }
+
+        // Refill the queue with any events that have already been recieved. The queue is
+        // empty, so any scale factor change events have been handled by now
+        let mut events = Vec::new();
+
+        let res = self.apply_suggested_sizes()
+            .and_then(|_| self.dispatch_queued(&mut |event| events.push(event)))
+            .and_then(|_| self.dispatch_redraws(false, &mut |event| events.push(event)))
+            .and_then(|_| self.shared.conn.flush().map_err(Error::X11FlushFailed));
+
+        if let Err(err) = res {
+            return Poll::Ready(Err(err));
+        }
+
+        let mut event_queue = self.event_queue.lock().unwrap();
+        event_queue.extend(events);
+
+        if let Some(event) = event_queue.pop_front() {
+            return Poll::Ready(Ok(self.yield_event(event)));
+        }
+
+        // Nothing recieved yet, wait for the connection to become readable
+        let mut poller = self.poller.lock().unwrap();
+
+        let poller = match &mut *poller {
+            Some(poller) => poller,
+
+            None => match AsyncPoller::new(self.shared.conn.as_raw_fd()) {
+                Ok(new_poller) => poller.insert(new_poller),
+                Err(err) => return Poll::Ready(Err(err))
+            }
+        };
+
+        poller.arm(cx.waker());
+
+        // Redraws requested while handling previous events don't make the connection
+        // readable, so make sure the task is polled again for them
+        if self.redraw_pending() {
+            cx.waker().wake_by_ref();
+        }
+
+        Poll::Pending
+    }
+
+    /// Keeps track of redraw events returned by `poll_event()`, so their frame can be
+    /// finished once they've been handled
+    #[cfg(feature = "async")]
+    fn yield_event(&self, event: OwnedEvent<T>) -> OwnedEvent<T> {
+        if let Event::WindowEvent { window_id, event: WindowEvent::RedrawRequested { .. } } = &event {
+            self.yielded_redraws.lock().unwrap().push(window_id.0 as u32);
+        }
+
+        event
+    }
+
+    /// Finishes the frames of redraw events previously returned by `poll_event()`
+    #[cfg(feature = "async")]
+    fn finish_yielded_redraws(&self) -> Result<(), Error> {
+        for window in mem::take(&mut *self.yielded_redraws.lock().unwrap()) {
+            let Some(state) = self.shared.window_state(window) else {
+                continue;
+            };
+
+            if let Some(sync) = state.sync.as_ref().filter(|sync| !sync.manual) {
+                sync.finish_frame(&self.shared.conn)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records whether the current thread is running an event loop that checks for
+    /// redraws before waiting
+    fn set_loop_thread(&self, checks_redraws: bool) {
+        *self.shared.loop_thread.lock().unwrap() = checks_redraws.then(|| thread::current().id());
+    }
+
+    /// Dispatches all events that have already been recieved without blocking
+    /// 
+    /// Returns whether any events were recieved
+    fn dispatch_queued(&self, func: &mut impl FnMut(OwnedEvent<T>)) -> Result<bool, Error> {
+        let mut recieved = false;
+
+        while let Some(x11_event) = self.shared.conn.poll_for_event().map_err(Error::X11PollForEventFailed)? {
+            self.dispatch(x11_event, func);
+            recieved = true;
+        }
+
+        Ok(recieved)
+    }
+
+    /// Sends a redraw event to every window with pending damage
+    /// 
+    /// If `finish_frames` is set, each frame is assumed to be complete once the closure
+    /// returns. Otherwise the caller has to finish the frames once the events have been
+    /// handled. Frames of windows with manual frame sync are finished by the application
+    fn dispatch_redraws(&self, finish_frames: bool, func: &mut impl FnMut(OwnedEvent<T>)) -> Result<(), Error> {
+        // Collect first so the closure is free to create and drop windows
+        let pending = self.shared.windows
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, state)| state.redraw_pending())
+            .map(|(window, state)| (*window, state.clone()))
+            .collect::<Vec<_>>();
+
+        for (window, state) in pending {
+            let damage = std::mem::take(&mut *state.damage.lock().unwrap());
+
+            if let Some(sync) = &state.sync {
+                sync.start_frame(&self.shared.conn)?;
+            }
+
+            func(Event::WindowEvent {
+                window_id: WindowId(window as u64),
+                event: WindowEvent::RedrawRequested { damage }
+            });
+
+            if let Some(sync) = state.sync.as_ref().filter(|sync| finish_frames && !sync.manual) {
+                sync.finish_frame(&self.shared.conn)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resizes windows to the sizes chosen while handling scale factor change events
+    fn apply_suggested_sizes(&self) -> Result<(), Error> {
+        for (window, suggested_size) in mem::take(&mut *self.suggested_sizes.lock().unwrap()) {
+            let Some(state) = self.shared.window_state(window) else {
+                continue;
+            };
+
+            let size = suggested_size.get();
+
+            if size != state.size() {
+                let aux = ConfigureWindowAux::new()
+                    .width(size.width)
+                    .height(size.height);
+
+                self.shared.conn.configure_window(window, &aux).map_err(Error::X11ConfigureWindowFailed)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn redraw_pending(&self) -> bool {
+        self.shared.windows
+            .lock()
+            .unwrap()
+            .values()
+            .any(|state| state.redraw_pending())
+    }
+
+    /// Blocks until the connection has data to read or the timeout expires
+    fn wait_readable(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        // Requests must be sent before waiting, otherwise their replies and errors never arrive
+        self.shared.conn.flush().map_err(Error::X11FlushFailed)?;
+
+        let mut poll_fd = libc::pollfd {
+            fd: self.shared.conn.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0
+        };
+
+        // Round up so we never wake up before the timeout
+        let timeout = match timeout {
+            Some(timeout) => timeout.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32,
+            None => -1
+        };
+
+        loop {
+            let res = unsafe { libc::poll(&mut poll_fd, 1, timeout) };
+
+            if res >= 0 {
+                return Ok(());
+            }
+
+            let err = io::Error::last_os_error();
+
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(Error::PollFailed(err));
+            }
+        }
+    }
+
+    /// Converts an X11 event into swil events and passes them to the given closure
+    fn dispatch(&self, x11_event: X11Event, func: &mut impl FnMut(OwnedEvent<T>)) {
+        // Wakeup from a proxy, deliver all queued user events
+        if let X11Event::ClientMessage(event) = &x11_event {
+            if event.window == self.shared.proxy_window {
+                // Don't hold the lock while the event is handled
+                loop {
+                    let Ok(user_event) = self.user_event_receiver.lock().unwrap().try_recv() else {
+                        break;
+                    };
+
+                    func(Event::User(user_event));
+                }
+
+                return;
+            }
+        }
+
+        // Monitors were added, removed or reconfigured, which can also change the scale
+        // factor of windows
+        if let X11Event::RandrScreenChangeNotify(_) | X11Event::RandrNotify(_) = &x11_event {
+            if self.update_monitors() {
+                func(Event::MonitorsChanged);
+
+                for (window, event) in self.update_all_scale_factors() {
+                    func(Event::WindowEvent { window_id: WindowId(window as u64), event });
+                }
+            }
+
+            return;
+        }
+
+        // Panels were added or removed, or the current desktop changed
+        if let X11Event::PropertyNotify(event) = &x11_event {
+            let atoms = &self.shared.atoms;
+            let mut gtk_work_areas_atom = self.shared.gtk_work_areas_atom.lock().unwrap();
+
+            let work_areas_changed = event.atom == atoms._NET_WORKAREA ||
+                                     event.atom == atoms._NET_CURRENT_DESKTOP ||
+                                     event.atom == *gtk_work_areas_atom;
+
+            if event.window == self.shared.root && work_areas_changed {
+                // Each desktop has its own GTK work areas
+                if event.atom == atoms._NET_CURRENT_DESKTOP {
+                    let desktop = monitor::current_desktop(&self.shared.conn, self.shared.root, atoms);
+                    *gtk_work_areas_atom = monitor::gtk_work_areas_atom(&self.shared.conn, desktop, false);
+                }
+
+                drop(gtk_work_areas_atom);
+
+                let mut monitors = self.shared.monitors.lock().unwrap();
+                monitor::set_work_areas(&self.shared.conn, self.shared.root, atoms, &mut monitors);
+
+                return;
+            }
+        }
+
+        // Changing the desktop's scaling affects every window
+        if let Some(changed) = self.update_scale_settings(&x11_event) {
+            if changed {
+                for (window, event) in self.update_all_scale_factors() {
+                    func(Event::WindowEvent { window_id: WindowId(window as u64), event });
+                }
+            }
+
+            return;
+        }
+
+        // Moving to another monitor can change the scale factor, which is reported after
+        // the window's other configure events
+        let scale_event = match &x11_event {
+            X11Event::ConfigureNotify(event) => self.update_scale_factor(event.window, event.width as u32, event.height as u32),
+            _ => None
+        };
+
+        if let Some((window, event)) = self.process_event(x11_event) {
+            func(Event::WindowEvent { window_id: WindowId(window as u64), event });
+        }
+
+        if let Some((window, event)) = scale_event {
+            func(Event::WindowEvent { window_id: WindowId(window as u64), event });
+        }
+    }
+
+    /// Updates the desktop scale settings if the event is a change to `Xft.dpi` or
+    /// XSETTINGS
+    /// 
+    /// Returns `None` if the event isn't related to scale settings, otherwise whether
+    /// the settings changed
+    fn update_scale_settings(&self, x11_event: &X11Event) -> Option<bool> {
+        let shared = &self.shared;
+        let atoms = &shared.atoms;
+        let mut xsettings_owner = shared.xsettings_owner.lock().unwrap();
+        let mut scale_settings = *shared.scale_settings.lock().unwrap();
+
+        match x11_event {
+            X11Event::PropertyNotify(event) if event.window == shared.root && event.atom == u32::from(AtomEnum::RESOURCE_MANAGER) => {
+                scale_settings.resource_dpi = settings::read_resource_dpi(&shared.conn);
+            },
+
+            X11Event::PropertyNotify(event) if Some(event.window) == *xsettings_owner && event.atom == atoms._XSETTINGS_SETTINGS => {
+                (scale_settings.xsettings_dpi, scale_settings.window_scaling_factor) =
+                    settings::read_xsettings(&shared.conn, event.window, atoms._XSETTINGS_SETTINGS);
+            },
+
+            // A new XSETTINGS manager has started
+            X11Event::ClientMessage(event) if event.window == shared.root && event.type_ == atoms.MANAGER && event.format == 32 => {
+                if event.data.as_data32()[1] != shared.xsettings_selection {
+                    return None;
+                }
+
+                *xsettings_owner = settings::watch_xsettings_owner(&shared.conn, shared.xsettings_selection);
+
+                (scale_settings.xsettings_dpi, scale_settings.window_scaling_factor) = match *xsettings_owner {
+                    Some(owner) => settings::read_xsettings(&shared.conn, owner, atoms._XSETTINGS_SETTINGS),
+                    None => (None, None)
+                };
+            },
+
+            // The XSETTINGS manager has exited
+            X11Event::DestroyNotify(event) if Some(event.window) == *xsettings_owner => {
+                *xsettings_owner = None;
+                scale_settings.xsettings_dpi = None;
+                scale_settings.window_scaling_factor = None;
+            },
+
+            _ => return None
+        }
+
+        let old_settings = std::mem::replace(&mut *shared.scale_settings.lock().unwrap(), scale_settings);
+
+        if old_settings.dpi() == scale_settings.dpi() {
+            return Some(false);
+        }
+
+        monitor::set_scale_factors(&mut shared.monitors.lock().unwrap(), &shared.env_scale_factors, scale_settings.dpi());
+
+        Some(true)
+    }
+
+    /// Queries the monitors again after a RandR change
+    /// 
+    /// Returns whether the monitors changed. Several RandR events are sent for a single
+    /// change, so the later ones usually don't change anything
+    fn update_monitors(&self) -> bool {
+        let shared = &self.shared;
+        let screen = &shared.conn.setup().roots[shared.screen_num];
+        let dpi = shared.scale_settings.lock().unwrap().dpi();
+
+        // Keep the old monitors if the query fails, for example because an output was
+        // removed while querying it
+        let Ok(monitors) = monitor::query_monitors(&shared.conn, screen, &shared.atoms, shared.monitor_sources, &shared.env_scale_factors, dpi) else {
+            return false;
+        };
+
+        let mut old_monitors = shared.monitors.lock().unwrap();
+
+        if *old_monitors == monitors {
+            return false;
+        }
+
+        *old_monitors = monitors;
+        true
+    }
+
+    /// Updates the scale factor of every window, returning scale factor change events
+    /// for the windows whose scale factor changed
+    fn update_all_scale_factors(&self) -> Vec<(u32, WindowEvent<'static>)> {
+        let windows = self.shared.windows
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(window, state)| (*window, state.size()))
+            .collect::<Vec<_>>();
+
+        windows
+            .into_iter()
+            .filter_map(|(window, size)| self.update_scale_factor(window, size.width, size.height))
+            .collect()
+    }
+
+    /// Checks which monitor a window is on after it was moved or resized, returning a
+    /// scale factor change event if the monitor's scale factor is different
+    fn update_scale_factor(&self, window: u32, width: u32, height: u32) -> Option<(u32, WindowEvent<'static>)> {
+        let shared = &self.shared;
+        let state = shared.window_state(window)?;
+        let old_scale_factor = *state.scale_factor.lock().unwrap();
+
+        // Overridden scale factors never change
+        if old_scale_factor.1 == ScaleFactorSource::Override {
+            return None;
+        }
+
+        if shared.uniform_scale_factor() && old_scale_factor == shared.primary_scale_factor() {
+            return None;
+        }
+
+        // Configure events from reparenting window managers are relative to the frame,
+        // so get the window's position on the root window
+        let coords = shared.conn
+            .translate_coordinates(window, shared.root, 0, 0)
+            .ok()?
+            .reply()
+            .ok()?;
+
+        let (scale_factor, source) = shared
+            .monitor_at(coords.dst_x as i32, coords.dst_y as i32, width, height)
+            .map(|monitor| (monitor.scale_factor(), monitor.scale_factor_source()))
+            .unwrap_or((1.0, ScaleFactorSource::Default));
+        let (old_scale_factor, _) = std::mem::replace(&mut *state.scale_factor.lock().unwrap(), (scale_factor, source));
+
+        if scale_factor == old_scale_factor {
+            return None;
+        }
+
+        // Keep the same logical size by default
+        let ratio = scale_factor / old_scale_factor;
+
+        let suggested_size = SuggestedSize::new(PhysicalSize {
+            width: (width as f32 * ratio).round() as u32,
+            height: (height as f32 * ratio).round() as u32
+        });
+
+        self.suggested_sizes.lock().unwrap().push((window, suggested_size.clone()));
+
+        Some((window, WindowEvent::ScaleFactorChanged { scale_factor, suggested_size }))
+    }
+
+    /// Converts an X11 event into a window event and the X11 window it was recieved by,
+    /// updating any window state tracked by the event loop
+    fn process_event(&self, x11_event: X11Event) -> Option<(u32, WindowEvent<'static>)> {
+        let shared = &self.shared;
+
+        match x11_event {
+            X11Event::ConfigureNotify(event) => {
+                let new_size = PhysicalSize { width: event.width as u32, height: event.height as u32 };
+                match shared.window_state(event.window) {
+                    Some(state) => {
+                        let old_size = std::mem::replace(&mut *state.size.lock().unwrap(), new_size);
+
+                        // A pending sync request is answered by the first frame drawn at
+                        // the new size, so queue a redraw of the whole window for it
+                        if let Some(sync) = &state.sync {
+                            let mut request = sync.request.lock().unwrap();
+
+                            if let Some(request) = request.as_mut().filter(|request| !request.configured) {
+                                request.configured = true;
+
+                                state.add_damage(Rect {
+                                    position: PhysicalPosition { x: 0, y: 0 },
+                                    size: new_size
+                                });
+                            }
+                        }
+
+                        if old_size != new_size {
+                            Some((event.window, WindowEvent::Resized(new_size)))
+                        }
+                        else {
+                            None
+                        }
+                    },
+
+                    None => None
+                }
+            },
+            
+            // Exposed regions are collected and sent as a single redraw event later
+            X11Event::Expose(event) => {
+                if let Some(state) = shared.window_state(event.window) {
+                    state.add_damage(Rect {
+                        position: PhysicalPosition { x: event.x as i32, y: event.y as i32 },
+                        size: PhysicalSize { width: event.width as u32, height: event.height as u32 }
+                    });
+                }
+
+                None
+            },
+
+            X11Event::ClientMessage(event) if event.format == 32 && event.type_ == shared.atoms.WM_PROTOCOLS => {
+                let data = event.data.as_data32();
+
+                if data[0] == shared.atoms.WM_DELETE_WINDOW {
+                    Some((event.window, WindowEvent::CloseRequested))
+                }
+                else if data[0] == shared.atoms._NET_WM_SYNC_REQUEST {
+                    // The window manager sends a configure event after the sync request,
+                    // and waits for a frame drawn after it
+                    if let Some(state) = shared.window_state(event.window) {
+                        if let Some(sync) = &state.sync {
+                            *sync.request.lock().unwrap() = Some(SyncRequest {
+                                serial: ((data[3] as u64) << 32) | data[2] as u64,
+                                extended: data[4] != 0,
+                                configured: false
+                            });
+                        }
+                    }
+
+                    None
+                }
+                else {
+                    None
+                }
+            },
+
+            // Sent by compositing window managers after a frame finished with the extended
+            // sync counter has been drawn
+            X11Event::ClientMessage(event) if event.format == 32 && event.type_ == shared.atoms._NET_WM_FRAME_DRAWN => {
+                shared.window_state(event.window)?;
+
+                let data = event.data.as_data32();
+
+                Some((event.window, WindowEvent::FrameDrawn {
+                    frame: ((data[1] as u64) << 32) | data[0] as u64,
+                    ust: ((data[3] as u64) << 32) | data[2] as u64
+                }))
+            },
+
+            X11Event::PresentCompleteNotify(event) => {
+                let state = shared.window_state(event.window)?;
+                let last_frame = state.last_frame.lock().unwrap().replace((event.msc, event.ust));
+
+                // Estimate the refresh interval from the previous frame
+                let refresh_interval = last_frame
+                    .filter(|(msc, ust)| event.msc > *msc && event.ust > *ust)
+                    .map(|(msc, ust)| Duration::from_micros((event.ust - ust) / (event.msc - msc)));
+
+                Some((event.window, WindowEvent::FramePresented {
+                    msc: event.msc,
+                    ust: event.ust,
+                    refresh_interval
+                }))
+            },
+
+            X11Event::PropertyNotify(event) if event.atom == shared.atoms._NET_FRAME_EXTENTS => {
+                let state = shared.window_state(event.window)?;
+
+                // The property is removed when the window manager stops decorating the window
+                let frame_extents = match monitor::read_cardinals(&shared.conn, event.window, event.atom) {
+                    Some(values) if values.len() >= 4 => FrameExtents {
+                        left: values[0],
+                        right: values[1],
+                        top: values[2],
+                        bottom: values[3]
+                    },
+
+                    _ => FrameExtents::default()
+                };
+
+                let old_frame_extents = std::mem::replace(&mut *state.frame_extents.lock().unwrap(), frame_extents);
+
+                if old_frame_extents != frame_extents {
+                    Some((event.window, WindowEvent::FrameExtentsChanged(frame_extents)))
+                }
+                else {
+                    None
+                }
+            },
+
+            X11Event::FocusIn(event) => Some((event.event, WindowEvent::FocusChanged(true))),
+            X11Event::FocusOut(event) => Some((event.event, WindowEvent::FocusChanged(false))),
+
+            X11Event::KeyPress(event) => {
+                Some((event.event, WindowEvent::KeyboardInput(KeyboardInput {
+                    code: event.detail,
+                    state: ButtonState::Pressed,
+                    text: None
+                })))
+            },
+
+            X11Event::KeyRelease(event) => {
+                Some((event.event, WindowEvent::KeyboardInput(KeyboardInput {
+                    code: event.detail,
+                    state: ButtonState::Released,
+                    text: None
+                })))
+            },
+
+            X11Event::MotionNotify(event) => Some((event.event, WindowEvent::CursorMoved(PhysicalPosition { x: event.event_x as i32, y: event.event_y as i32 }))),
+
+            X11Event::LeaveNotify(event) => Some((event.event, WindowEvent::CursorLeft)),
+            X11Event::EnterNotify(event) => Some((event.event, WindowEvent::CursorEntered)),
+
+            X11Event::ButtonPress(event) => {
+                *shared.last_button_press.lock().unwrap() = Some((event.root_x, event.root_y, event.detail));
+                map_button_event(event.detail, ButtonState::Pressed).map(|e| (event.event, e))
+            },
+
+            X11Event::ButtonRelease(event) => {
+                *shared.last_button_press.lock().unwrap() = None;
+                map_button_event(event.detail, ButtonState::Released).map(|e| (event.event, e))
+            },
+
+            _ => None
+        }
+    }
+}
+
+impl<T> AsRawFd for EventLoop<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.shared.conn.as_raw_fd()
+    }
+}
+
+impl<T> AsFd for EventLoop<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // The fd stays open for as long as the connection, which the event loop keeps alive
+        unsafe { BorrowedFd::borrow_raw(self.shared.conn.as_raw_fd()) }
+    }
+}
+
+impl<T> Drop for EventLoop<T> {
+    fn drop(&mut self) {
+        // Stop the poller thread before the connection can be closed, since it's waiting
+        // on the connection's file descriptor
+        #[cfg(feature = "async")]
+        self.poller.get_mut().unwrap().take();
+
+        self.shared.conn.destroy_window(self.shared.proxy_window).unwrap();
+        self.shared.conn.flush().unwrap();
+    }
+}
+
+pub struct EventLoopProxy<T> {
+    shared: Arc<Shared>,
+    sender: Sender<T>
+}
+
+impl<T> EventLoopProxy<T> {
+    pub fn send_event(&self, event: T) -> Result<(), Error> {
+        self.sender.send(event).map_err(|_| Error::EventLoopClosed)?;
+        self.shared.wake_up()
+    }
+}
+
+impl<T> Clone for EventLoopProxy<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            sender: self.sender.clone()
+        }
+    }
+}
+
+fn to_int64(value: u64) -> Int64 {
+    Int64 {
+        hi: (value >> 32) as i32,
+        lo: value as u32
+    }
+}
+
+fn map_button_event<'a>(button: u8, state: ButtonState) -> Option<WindowEvent<'a>> {
+    match button {
+        1 => Some(WindowEvent::MouseInput(MouseInput { button: MouseButton::Left, state })),
+        2 => Some(WindowEvent::MouseInput(MouseInput { button: MouseButton::Middle, state })),
+        3 => Some(WindowEvent::MouseInput(MouseInput { button: MouseButton::Right, state })),
+
+        4 => if let ButtonState::Pressed = state {
+            Some(WindowEvent::MouseScroll(MouseScroll::Up))
+        }
+        else {
+            None
+        },
+
+        5 => if let ButtonState::Pressed = state {
+            Some(WindowEvent::MouseScroll(MouseScroll::Down))
+        }
+        else {
+            None
+        },
+
+        6 => if let ButtonState::Pressed = state {
+            Some(WindowEvent::MouseScroll(MouseScroll::Left))
+        }
+        else {
+            None
+        },
+
+        7 => if let ButtonState::Pressed = state {
+            Some(WindowEvent::MouseScroll(MouseScroll::Right))
+        }
+        else {
+            None
+        },
+
+        other => Some(WindowEvent::MouseInput(MouseInput { button: MouseButton::Other(other), state }))
+    }
+}
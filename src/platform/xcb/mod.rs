@@ -0,0 +1,37 @@
+mod event_loop;
+mod window;
+mod monitor;
+mod settings;
+
+#[cfg(feature = "async")]
+mod async_poller;
+
+use x11rb::atom_manager;
+
+pub use event_loop::{EventLoop, EventLoopProxy};
+pub use window::{Window, ChildWindow};
+pub use monitor::{MonitorHandle, VideoMode};
+
+use event_loop::{Shared, WindowState, SyncState};
+use settings::ScaleSettings;
+
+atom_manager! {
+    pub AtomSet: AtomSetCookie {
+        WM_PROTOCOLS,
+        WM_DELETE_WINDOW,
+        _NET_WM_SYNC_REQUEST,
+        _NET_WM_SYNC_REQUEST_COUNTER,
+        _NET_WM_FRAME_DRAWN,
+        _NET_WM_MOVERESIZE,
+        _GTK_SHOW_WINDOW_MENU,
+        _NET_WM_WINDOW_OPACITY,
+        _KDE_NET_WM_BLUR_BEHIND_REGION,
+        _XSETTINGS_SETTINGS,
+        _NET_WORKAREA,
+        _NET_CURRENT_DESKTOP,
+        _NET_FRAME_EXTENTS,
+        _NET_REQUEST_FRAME_EXTENTS,
+        MANAGER,
+        _SWIL_WAKEUP,
+    }
+}
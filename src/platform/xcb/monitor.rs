@@ -0,0 +1,498 @@
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        randr::{self, ConnectionExt as _, ModeFlag},
+        xinerama::ConnectionExt as _,
+        xproto::{ConnectionExt as _, Screen, AtomEnum}
+    }
+};
+
+use crate::{
+    Error,
+    dpi::{PhysicalPosition, PhysicalSize, Rect, ScaleFactorSource}
+};
+
+use super::{AtomSet, settings::EnvScaleFactors};
+
+/// Geometry and scale of a single monitor, in root window coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorHandle {
+    name: String,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    width_mm: u32,
+    height_mm: u32,
+    refresh_rate_millihertz: Option<u32>,
+    primary: bool,
+    scale_factor: f32,
+    scale_factor_source: ScaleFactorSource,
+    video_modes: Vec<VideoMode>,
+
+    /// RandR mode ID of the current mode
+    current_mode: Option<u32>,
+
+    /// Area not covered by panels and docks, as `(x, y, width, height)`
+    work_area: (i32, i32, u32, u32)
+}
+
+impl MonitorHandle {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn position(&self) -> PhysicalPosition {
+        PhysicalPosition { x: self.x, y: self.y }
+    }
+
+    pub fn size(&self) -> PhysicalSize {
+        PhysicalSize { width: self.width, height: self.height }
+    }
+
+    pub fn physical_size_mm(&self) -> (u32, u32) {
+        (self.width_mm, self.height_mm)
+    }
+
+    pub fn refresh_rate_millihertz(&self) -> Option<u32> {
+        self.refresh_rate_millihertz
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    pub fn scale_factor_source(&self) -> ScaleFactorSource {
+        self.scale_factor_source
+    }
+
+    pub fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    pub fn work_area(&self) -> Rect {
+        let (x, y, width, height) = self.work_area;
+
+        Rect {
+            position: PhysicalPosition { x, y },
+            size: PhysicalSize { width, height }
+        }
+    }
+
+    pub fn video_modes(&self) -> &[VideoMode] {
+        &self.video_modes
+    }
+
+    pub fn current_video_mode(&self) -> Option<&VideoMode> {
+        let current_mode = self.current_mode?;
+
+        self.video_modes.iter().find(|mode| mode.mode == current_mode)
+    }
+
+    /// Gets the area of the given rectangle that lies on this monitor
+    pub fn overlap(&self, x: i32, y: i32, width: u32, height: u32) -> u64 {
+        intersect((self.x, self.y, self.width, self.height), (x, y, width, height))
+            .map(|(_, _, width, height)| width as u64 * height as u64)
+            .unwrap_or(0)
+    }
+}
+
+/// A display mode supported by a monitor
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoMode {
+    size: PhysicalSize,
+    bit_depth: u16,
+    refresh_rate_millihertz: u32,
+
+    /// RandR mode ID
+    mode: u32
+}
+
+impl VideoMode {
+    pub fn size(&self) -> PhysicalSize {
+        self.size
+    }
+
+    pub fn bit_depth(&self) -> u16 {
+        self.bit_depth
+    }
+
+    pub fn refresh_rate_millihertz(&self) -> u32 {
+        self.refresh_rate_millihertz
+    }
+
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+}
+
+/// Extensions that monitors can be queried from
+#[derive(Debug, Clone, Copy)]
+pub struct MonitorSources {
+    /// RandR 1.5 or later, which provides `GetMonitors`
+    pub randr: bool,
+    pub xinerama: bool
+}
+
+/// Queries the monitors using RandR, falling back to Xinerama and then to treating the
+/// whole screen as a single monitor
+/// 
+/// Scale factors are chosen as described in [`set_scale_factors()`]
+pub fn query_monitors(
+    conn: &impl Connection,
+    screen: &Screen,
+    atoms: &AtomSet,
+    sources: MonitorSources,
+    env_scale_factors: &EnvScaleFactors,
+    dpi: Option<f32>
+) -> Result<Vec<MonitorHandle>, Error> {
+    let mut monitors = Vec::new();
+
+    if sources.randr {
+        monitors = query_randr_monitors(conn, screen)?;
+    }
+
+    if monitors.is_empty() && sources.xinerama {
+        monitors = query_xinerama_monitors(conn, screen)?;
+    }
+
+    if monitors.is_empty() {
+        monitors.push(MonitorHandle {
+            name: String::from("default"),
+            x: 0,
+            y: 0,
+            width: screen.width_in_pixels as u32,
+            height: screen.height_in_pixels as u32,
+            width_mm: screen.width_in_millimeters as u32,
+            height_mm: screen.height_in_millimeters as u32,
+            refresh_rate_millihertz: None,
+            primary: true,
+            scale_factor: 1.0,
+            scale_factor_source: ScaleFactorSource::Default,
+            video_modes: Vec::new(),
+            current_mode: None,
+            work_area: (0, 0, 0, 0)
+        });
+    }
+
+    set_scale_factors(&mut monitors, env_scale_factors, dpi);
+    set_work_areas(conn, screen.root, atoms, &mut monitors);
+
+    Ok(monitors)
+}
+
+/// Queries the monitors like [`query_monitors()`], but skips a source if querying it
+/// fails, ending with the whole screen as a single monitor
+/// 
+/// Used when there are no previous monitors to fall back on
+pub fn query_monitors_or_fallback(
+    conn: &impl Connection,
+    screen: &Screen,
+    atoms: &AtomSet,
+    sources: MonitorSources,
+    env_scale_factors: &EnvScaleFactors,
+    dpi: Option<f32>
+) -> Vec<MonitorHandle> {
+    let without_randr = MonitorSources { randr: false, ..sources };
+    let screen_only = MonitorSources { randr: false, xinerama: false };
+
+    // Querying the screen alone doesn't send any requests that can fail
+    query_monitors(conn, screen, atoms, sources, env_scale_factors, dpi)
+        .or_else(|_| query_monitors(conn, screen, atoms, without_randr, env_scale_factors, dpi))
+        .or_else(|_| query_monitors(conn, screen, atoms, screen_only, env_scale_factors, dpi))
+        .unwrap_or_default()
+}
+
+/// Updates the work area of every monitor from the window manager's hints
+/// 
+/// `_GTK_WORKAREAS_Dn` lists a work area per monitor for desktop `n`, while
+/// `_NET_WORKAREA` only has a single area per desktop spanning all monitors. Monitors
+/// without either use their whole area
+pub fn set_work_areas(conn: &impl Connection, root: u32, atoms: &AtomSet, monitors: &mut [MonitorHandle]) {
+    let desktop = current_desktop(conn, root, atoms);
+    let gtk_atom = gtk_work_areas_atom(conn, desktop, true);
+
+    let gtk_work_areas = match gtk_atom {
+        x11rb::NONE => Vec::new(),
+        atom => read_cardinals(conn, root, atom).unwrap_or_default()
+    };
+
+    let net_work_area = read_cardinals(conn, root, atoms._NET_WORKAREA)
+        .and_then(|values| values.chunks_exact(4).nth(desktop as usize).map(to_area));
+
+    for monitor in monitors {
+        let monitor_area = (monitor.x, monitor.y, monitor.width, monitor.height);
+
+        let gtk_work_area = gtk_work_areas
+            .chunks_exact(4)
+            .map(to_area)
+            .filter(|area| intersect(monitor_area, *area).is_some())
+            .max_by_key(|area| monitor.overlap(area.0, area.1, area.2, area.3));
+
+        monitor.work_area = gtk_work_area
+            .and_then(|area| intersect(monitor_area, area))
+            .or_else(|| net_work_area.and_then(|area| intersect(monitor_area, area)))
+            .unwrap_or(monitor_area);
+    }
+}
+
+/// Gets the index of the current desktop, or 0 if the window manager doesn't say
+pub fn current_desktop(conn: &impl Connection, root: u32, atoms: &AtomSet) -> u32 {
+    read_cardinals(conn, root, atoms._NET_CURRENT_DESKTOP)
+        .and_then(|values| values.first().copied())
+        .unwrap_or(0)
+}
+
+/// Gets the `_GTK_WORKAREAS_Dn` atom for the given desktop
+/// 
+/// If `only_if_exists` is set and the atom hasn't been created yet, `NONE` is returned
+pub fn gtk_work_areas_atom(conn: &impl Connection, desktop: u32, only_if_exists: bool) -> u32 {
+    conn
+        .intern_atom(only_if_exists, format!("_GTK_WORKAREAS_D{desktop}").as_bytes())
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .map(|reply| reply.atom)
+        .unwrap_or(x11rb::NONE)
+}
+
+/// Reads a property made of 32 bit values
+pub fn read_cardinals(conn: &impl Connection, window: u32, property: u32) -> Option<Vec<u32>> {
+    let reply = conn
+        .get_property(false, window, property, AtomEnum::CARDINAL, 0, u32::MAX / 4)
+        .ok()?
+        .reply()
+        .ok()?;
+
+    let values = reply.value32()?.collect();
+
+    Some(values)
+}
+
+fn to_area(values: &[u32]) -> (i32, i32, u32, u32) {
+    (values[0] as i32, values[1] as i32, values[2], values[3])
+}
+
+/// Gets the intersection of two areas, if they overlap
+fn intersect(a: (i32, i32, u32, u32), b: (i32, i32, u32, u32)) -> Option<(i32, i32, u32, u32)> {
+    let left = a.0.max(b.0);
+    let top = a.1.max(b.1);
+    let right = (a.0 + a.2 as i32).min(b.0 + b.2 as i32);
+    let bottom = (a.1 + a.3 as i32).min(b.1 + b.3 as i32);
+
+    if right > left && bottom > top {
+        Some((left, top, (right - left) as u32, (bottom - top) as u32))
+    }
+    else {
+        None
+    }
+}
+
+/// Updates the scale factor of every monitor after the desktop's DPI changed
+/// 
+/// `SWIL_SCALE_FACTOR` takes priority, then the desktop's DPI, and otherwise the scale
+/// factor is calculated from each monitor's physical size
+pub fn set_scale_factors(monitors: &mut [MonitorHandle], env_scale_factors: &EnvScaleFactors, dpi: Option<f32>) {
+    for monitor in monitors {
+        let size_scale_factor = || scale_factor_from_size(monitor.width, monitor.height, monitor.width_mm, monitor.height_mm);
+
+        (monitor.scale_factor, monitor.scale_factor_source) = if let Some(scale_factor) = env_scale_factors.get(&monitor.name) {
+            (scale_factor, ScaleFactorSource::Environment)
+        }
+        else if let Some(dpi) = dpi {
+            (dpi / 96.0, ScaleFactorSource::Xft)
+        }
+        else if let Some(scale_factor) = size_scale_factor() {
+            (scale_factor, ScaleFactorSource::RandR)
+        }
+        else {
+            (1.0, ScaleFactorSource::Default)
+        };
+    }
+}
+
+fn query_randr_monitors(conn: &impl Connection, screen: &Screen) -> Result<Vec<MonitorHandle>, Error> {
+    let reply = conn
+        .randr_get_monitors(screen.root, true)
+        .map_err(|err| Error::X11MonitorQueryFailed(err.into()))?
+        .reply()
+        .map_err(Error::X11MonitorQueryFailed)?;
+
+    // Needed to look up the modes of each monitor's outputs
+    let resources = conn
+        .randr_get_screen_resources_current(screen.root)
+        .map_err(|err| Error::X11MonitorQueryFailed(err.into()))?
+        .reply()
+        .map_err(Error::X11MonitorQueryFailed)?;
+
+    let mut monitors = Vec::new();
+
+    for info in reply.monitors {
+        let name = conn
+            .get_atom_name(info.name)
+            .map_err(|err| Error::X11MonitorQueryFailed(err.into()))?
+            .reply()
+            .map_err(Error::X11MonitorQueryFailed)?
+            .name;
+
+        // Monitors can span several outputs, which all show the same mode
+        let (video_modes, current_mode) = match info.outputs.first() {
+            Some(&output) => output_modes(conn, screen, &resources, output)?,
+            None => (Vec::new(), None)
+        };
+
+        let refresh_rate_millihertz = video_modes
+            .iter()
+            .find(|mode| Some(mode.mode) == current_mode)
+            .map(|mode| mode.refresh_rate_millihertz)
+            .filter(|refresh_rate| *refresh_rate != 0);
+
+        monitors.push(MonitorHandle {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            x: info.x as i32,
+            y: info.y as i32,
+            width: info.width as u32,
+            height: info.height as u32,
+            width_mm: info.width_in_millimeters,
+            height_mm: info.height_in_millimeters,
+            refresh_rate_millihertz,
+            primary: info.primary,
+            scale_factor: 1.0,
+            scale_factor_source: ScaleFactorSource::Default,
+            video_modes,
+            current_mode,
+            work_area: (0, 0, 0, 0)
+        });
+    }
+
+    Ok(monitors)
+}
+
+fn query_xinerama_monitors(conn: &impl Connection, screen: &Screen) -> Result<Vec<MonitorHandle>, Error> {
+    let active = conn
+        .xinerama_is_active()
+        .map_err(|err| Error::X11MonitorQueryFailed(err.into()))?
+        .reply()
+        .map_err(Error::X11MonitorQueryFailed)?
+        .state != 0;
+
+    if !active {
+        return Ok(Vec::new());
+    }
+
+    let reply = conn
+        .xinerama_query_screens()
+        .map_err(|err| Error::X11MonitorQueryFailed(err.into()))?
+        .reply()
+        .map_err(Error::X11MonitorQueryFailed)?;
+
+    // Xinerama doesn't report physical sizes, so estimate them from the screen's DPI
+    let width_mm = |width: u16| (width as u64 * screen.width_in_millimeters as u64 / screen.width_in_pixels.max(1) as u64) as u32;
+    let height_mm = |height: u16| (height as u64 * screen.height_in_millimeters as u64 / screen.height_in_pixels.max(1) as u64) as u32;
+
+    let monitors = reply.screen_info
+        .iter()
+        .enumerate()
+        .map(|(i, info)| MonitorHandle {
+            name: format!("Xinerama-{i}"),
+            x: info.x_org as i32,
+            y: info.y_org as i32,
+            width: info.width as u32,
+            height: info.height as u32,
+            width_mm: width_mm(info.width),
+            height_mm: height_mm(info.height),
+            refresh_rate_millihertz: None,
+            primary: i == 0,
+            scale_factor: 1.0,
+            scale_factor_source: ScaleFactorSource::Default,
+            video_modes: Vec::new(),
+            current_mode: None,
+            work_area: (0, 0, 0, 0)
+        })
+        .collect();
+
+    Ok(monitors)
+}
+
+/// Gets the modes supported by an output, and the ID of the mode it is currently showing
+fn output_modes(
+    conn: &impl Connection,
+    screen: &Screen,
+    resources: &randr::GetScreenResourcesCurrentReply,
+    output: u32
+) -> Result<(Vec<VideoMode>, Option<u32>), Error> {
+    let output_info = conn
+        .randr_get_output_info(output, resources.config_timestamp)
+        .map_err(|err| Error::X11MonitorQueryFailed(err.into()))?
+        .reply()
+        .map_err(Error::X11MonitorQueryFailed)?;
+
+    // Mode details are only listed in the screen resources
+    let video_modes = output_info.modes
+        .iter()
+        .filter_map(|id| resources.modes.iter().find(|mode| mode.id == *id))
+        .map(|mode| VideoMode {
+            size: PhysicalSize { width: mode.width as u32, height: mode.height as u32 },
+            bit_depth: screen.root_depth as u16,
+            refresh_rate_millihertz: mode_refresh_rate(mode).unwrap_or(0),
+            mode: mode.id
+        })
+        .collect();
+
+    if output_info.crtc == x11rb::NONE {
+        return Ok((video_modes, None));
+    }
+
+    let crtc_info = conn
+        .randr_get_crtc_info(output_info.crtc, resources.config_timestamp)
+        .map_err(|err| Error::X11MonitorQueryFailed(err.into()))?
+        .reply()
+        .map_err(Error::X11MonitorQueryFailed)?;
+
+    Ok((video_modes, Some(crtc_info.mode)))
+}
+
+/// Calculates the refresh rate of a mode in millihertz
+fn mode_refresh_rate(mode: &randr::ModeInfo) -> Option<u32> {
+    let mut vtotal = mode.vtotal as u64;
+
+    if mode.mode_flags.contains(ModeFlag::DOUBLE_SCAN) {
+        vtotal *= 2;
+    }
+
+    if mode.mode_flags.contains(ModeFlag::INTERLACE) {
+        vtotal /= 2;
+    }
+
+    let total = mode.htotal as u64 * vtotal;
+
+    if total == 0 {
+        return None;
+    }
+
+    Some((mode.dot_clock as u64 * 1000 / total) as u32)
+}
+
+/// Calculates a scale factor from a monitor's physical DPI
+/// 
+/// Returns `None` if the monitor's physical size is unknown or unrealistic
+fn scale_factor_from_size(width: u32, height: u32, width_mm: u32, height_mm: u32) -> Option<f32> {
+    // Some monitors, projectors and virtual outputs don't report a physical size
+    if width_mm == 0 || height_mm == 0 {
+        return None;
+    }
+
+    // This approach is taken from winit: https://github.com/rust-windowing/winit/blob/7bed5eecfdcbde16e5619fd137f0229e8e7e8ed4/src/platform_impl/linux/x11/util/randr.rs#L16
+    let ppmm = (
+        (width as f32 * height as f32) /
+        (width_mm as f32 * height_mm as f32)
+    ).sqrt();
+
+    // Quantize 1/12 step size
+    let dpi_factor = ((ppmm * (12.0 * 25.4 / 96.0)).round() / 12.0).max(1.0);
+
+    if dpi_factor <= 20.0 {
+        Some(dpi_factor)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,367 @@
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{ConnectionExt as _, AtomEnum, ChangeWindowAttributesAux, EventMask}
+};
+
+/// Desktop settings that affect the scale factor
+/// 
+/// These come from the `Xft.dpi` resource and from the XSETTINGS manager, both of which
+/// are updated by desktop environments when the user changes the scaling
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScaleSettings {
+    /// `Xft.dpi` from the resource database
+    pub resource_dpi: Option<u32>,
+
+    /// `Xft/DPI` from XSETTINGS, in 1024ths of a DPI
+    pub xsettings_dpi: Option<i32>,
+
+    /// `Gdk/WindowScalingFactor` from XSETTINGS
+    pub window_scaling_factor: Option<i32>
+}
+
+impl ScaleSettings {
+    /// Gets the DPI that overrides the per monitor scale factor, if any
+    pub fn dpi(&self) -> Option<f32> {
+        if let Some(dpi) = self.resource_dpi {
+            return Some(dpi as f32);
+        }
+
+        // Xft/DPI already includes the window scaling factor
+        match (self.xsettings_dpi, self.window_scaling_factor) {
+            (Some(dpi), _) if dpi > 0 => Some(dpi as f32 / 1024.0),
+            (_, Some(factor)) if factor > 0 => Some(factor as f32 * 96.0),
+            _ => None
+        }
+    }
+}
+
+/// Reads `Xft.dpi` from the resource database
+pub fn read_resource_dpi(conn: &impl Connection) -> Option<u32> {
+    x11rb::resource_manager::new_from_default(conn)
+        .ok()
+        .and_then(|db| db.get_value::<u32>("Xft.dpi", "").ok())
+        .flatten()
+}
+
+/// Gets the current XSETTINGS manager window and watches it for changes
+/// 
+/// Returns `None` if no XSETTINGS manager is running
+pub fn watch_xsettings_owner(conn: &impl Connection, selection: u32) -> Option<u32> {
+    let owner = conn
+        .get_selection_owner(selection)
+        .ok()?
+        .reply()
+        .ok()?
+        .owner;
+
+    if owner == x11rb::NONE {
+        return None;
+    }
+
+    // Property changes signal new settings, and the manager window is destroyed when
+    // the manager exits
+    let aux = ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE | EventMask::STRUCTURE_NOTIFY);
+    conn.change_window_attributes(owner, &aux).ok()?;
+
+    Some(owner)
+}
+
+/// Reads the scale related settings from the XSETTINGS manager window
+/// 
+/// Returns `Xft/DPI` and `Gdk/WindowScalingFactor`
+pub fn read_xsettings(conn: &impl Connection, owner: u32, settings_atom: u32) -> (Option<i32>, Option<i32>) {
+    let reply = conn
+        .get_property(false, owner, settings_atom, AtomEnum::ANY, 0, u32::MAX / 4)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok());
+
+    let mut dpi = None;
+    let mut scaling_factor = None;
+
+    if let Some(reply) = reply {
+        for (name, value) in parse_xsettings(&reply.value) {
+            match name {
+                b"Xft/DPI" => dpi = Some(value),
+                b"Gdk/WindowScalingFactor" => scaling_factor = Some(value),
+                _ => ()
+            }
+        }
+    }
+
+    (dpi, scaling_factor)
+}
+
+/// Parses the integer settings from an `_XSETTINGS_SETTINGS` property
+/// 
+/// The format is described in the XSETTINGS spec: https://specifications.freedesktop.org/xsettings-spec/0.5/
+fn parse_xsettings(data: &[u8]) -> Vec<(&[u8], i32)> {
+    let mut settings = Vec::new();
+
+    let Some(&byte_order) = data.first() else {
+        return settings;
+    };
+
+    let read_u16 = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1]];
+        if byte_order == 0 { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) }
+    };
+
+    let read_u32 = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if byte_order == 0 { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) }
+    };
+
+    // Header is the byte order, 3 bytes of padding, the serial and the number of settings
+    if data.len() < 12 {
+        return settings;
+    }
+
+    let count = read_u32(&data[8..]);
+    let mut offset = 12;
+
+    for _ in 0..count {
+        // Setting header is the type, 1 byte of padding and the name length
+        let Some(header) = data.get(offset..offset + 4) else {
+            break;
+        };
+
+        let kind = header[0];
+        let name_len = read_u16(&header[2..]) as usize;
+        let name_start = offset + 4;
+
+        // Names are padded to 4 bytes, followed by the last change serial
+        let value_start = name_start + name_len.next_multiple_of(4) + 4;
+
+        let Some(name) = data.get(name_start..name_start + name_len) else {
+            break;
+        };
+
+        let value_len = match kind {
+            // Integer
+            0 => {
+                let Some(value) = data.get(value_start..value_start + 4) else {
+                    break;
+                };
+
+                settings.push((name, read_u32(value) as i32));
+                4
+            },
+
+            // String, prefixed by its length and padded to 4 bytes
+            1 => {
+                let Some(len) = data.get(value_start..value_start + 4) else {
+                    break;
+                };
+
+                4 + (read_u32(len) as usize).next_multiple_of(4)
+            },
+
+            // Color, 4 16 bit channels
+            2 => 8,
+
+            _ => break
+        };
+
+        offset = value_start + value_len;
+    }
+
+    settings
+}
+
+/// Scale factors set with the `SWIL_SCALE_FACTOR` environment variable
+/// 
+/// The variable is either a single scale factor for every monitor, or a `;` separated
+/// list of `name=scale_factor` entries. An entry without a name applies to monitors
+/// that aren't listed. Invalid entries are ignored
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EnvScaleFactors {
+    all: Option<f32>,
+    monitors: Vec<(String, f32)>
+}
+
+impl EnvScaleFactors {
+    /// Reads the `SWIL_SCALE_FACTOR` environment variable
+    pub fn from_env() -> Self {
+        std::env::var("SWIL_SCALE_FACTOR")
+            .map(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+
+    fn parse(value: &str) -> Self {
+        let mut env_scale_factors = Self::default();
+
+        let parse_scale_factor = |value: &str| {
+            value
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|scale_factor| scale_factor.is_finite() && *scale_factor > 0.0)
+        };
+
+        for entry in value.split(';') {
+            match entry.split_once('=') {
+                Some((name, scale_factor)) => {
+                    if let Some(scale_factor) = parse_scale_factor(scale_factor) {
+                        env_scale_factors.monitors.push((name.trim().to_owned(), scale_factor));
+                    }
+                },
+
+                None => {
+                    if let Some(scale_factor) = parse_scale_factor(entry) {
+                        env_scale_factors.all = Some(scale_factor);
+                    }
+                }
+            }
+        }
+
+        env_scale_factors
+    }
+
+    /// Gets the scale factor set for a monitor, if any
+    pub fn get(&self, monitor_name: &str) -> Option<f32> {
+        self.monitors
+            .iter()
+            .find(|(name, _)| name == monitor_name)
+            .map(|(_, scale_factor)| *scale_factor)
+            .or(self.all)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A setting in an `_XSETTINGS_SETTINGS` blob
+    enum Setting<'a> {
+        Int(&'a str, i32),
+        String(&'a str, &'a str),
+        Color(&'a str, [u16; 4])
+    }
+
+    /// Encodes settings as an `_XSETTINGS_SETTINGS` blob with the given byte order
+    fn xsettings_blob(big_endian: bool, settings: &[Setting]) -> Vec<u8> {
+        let u16_bytes = |value: u16| if big_endian { value.to_be_bytes() } else { value.to_le_bytes() };
+        let u32_bytes = |value: u32| if big_endian { value.to_be_bytes() } else { value.to_le_bytes() };
+
+        let pad = |data: &mut Vec<u8>| data.resize(data.len().next_multiple_of(4), 0);
+
+        let mut data = vec![big_endian as u8, 0, 0, 0];
+        data.extend(u32_bytes(7));
+        data.extend(u32_bytes(settings.len() as u32));
+
+        for setting in settings {
+            let (kind, name) = match setting {
+                Setting::Int(name, _) => (0, name),
+                Setting::String(name, _) => (1, name),
+                Setting::Color(name, _) => (2, name)
+            };
+
+            data.extend([kind, 0]);
+            data.extend(u16_bytes(name.len() as u16));
+            data.extend(name.as_bytes());
+            pad(&mut data);
+            data.extend(u32_bytes(3));
+
+            match setting {
+                Setting::Int(_, value) => data.extend(u32_bytes(*value as u32)),
+
+                Setting::String(_, value) => {
+                    data.extend(u32_bytes(value.len() as u32));
+                    data.extend(value.as_bytes());
+                    pad(&mut data);
+                },
+
+                Setting::Color(_, channels) => {
+                    for channel in channels {
+                        data.extend(u16_bytes(*channel));
+                    }
+                }
+            }
+        }
+
+        data
+    }
+
+    fn dpi_settings() -> [Setting<'static>; 4] {
+        [
+            Setting::String("Net/ThemeName", "Adwaita"),
+            Setting::Color("Gtk/ColorScheme", [0x1234, 0x5678, 0x9abc, 0xffff]),
+            Setting::Int("Gdk/WindowScalingFactor", 2),
+            Setting::Int("Xft/DPI", 96 * 1024 * 2)
+        ]
+    }
+
+    #[test]
+    fn xsettings_little_endian() {
+        let data = xsettings_blob(false, &dpi_settings());
+
+        assert_eq!(parse_xsettings(&data), [
+            (&b"Gdk/WindowScalingFactor"[..], 2),
+            (&b"Xft/DPI"[..], 96 * 1024 * 2)
+        ]);
+    }
+
+    #[test]
+    fn xsettings_big_endian() {
+        let data = xsettings_blob(true, &dpi_settings());
+
+        assert_eq!(parse_xsettings(&data), [
+            (&b"Gdk/WindowScalingFactor"[..], 2),
+            (&b"Xft/DPI"[..], 96 * 1024 * 2)
+        ]);
+    }
+
+    #[test]
+    fn xsettings_truncated() {
+        let data = xsettings_blob(false, &dpi_settings());
+
+        // Settings cut off part way are skipped, along with everything after them
+        assert_eq!(parse_xsettings(&data[..data.len() - 2]), [(&b"Gdk/WindowScalingFactor"[..], 2)]);
+        assert_eq!(parse_xsettings(&data[..20]), []);
+        assert_eq!(parse_xsettings(&data[..8]), []);
+        assert_eq!(parse_xsettings(&[]), []);
+    }
+
+    #[test]
+    fn env_scale_factors_named() {
+        let env_scale_factors = EnvScaleFactors::parse("DP-1=2;HDMI-1=1.5");
+
+        assert_eq!(env_scale_factors.get("DP-1"), Some(2.0));
+        assert_eq!(env_scale_factors.get("HDMI-1"), Some(1.5));
+        assert_eq!(env_scale_factors.get("eDP-1"), None);
+    }
+
+    #[test]
+    fn env_scale_factors_fallback() {
+        let env_scale_factors = EnvScaleFactors::parse("1.25;DP-1=2");
+
+        assert_eq!(env_scale_factors.get("DP-1"), Some(2.0));
+        assert_eq!(env_scale_factors.get("eDP-1"), Some(1.25));
+
+        let env_scale_factors = EnvScaleFactors::parse("1.75");
+        assert_eq!(env_scale_factors.get("DP-1"), Some(1.75));
+    }
+
+    #[test]
+    fn env_scale_factors_whitespace() {
+        let env_scale_factors = EnvScaleFactors::parse(" DP-1 = 2 ; 1.5 ");
+
+        assert_eq!(env_scale_factors.get("DP-1"), Some(2.0));
+        assert_eq!(env_scale_factors.get("eDP-1"), Some(1.5));
+    }
+
+    #[test]
+    fn env_scale_factors_invalid() {
+        let env_scale_factors = EnvScaleFactors::parse("DP-1=abc;HDMI-1=-2;eDP-1=NaN;DP-2=inf;DP-3=0;;=");
+
+        for name in ["DP-1", "HDMI-1", "eDP-1", "DP-2", "DP-3", ""] {
+            assert_eq!(env_scale_factors.get(name), None);
+        }
+
+        // Invalid fallbacks don't replace a valid one
+        let env_scale_factors = EnvScaleFactors::parse("2;-1;NaN;abc");
+        assert_eq!(env_scale_factors.get("DP-1"), Some(2.0));
+
+        assert_eq!(EnvScaleFactors::parse("").get("DP-1"), None);
+    }
+}
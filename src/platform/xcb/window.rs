@@ -0,0 +1,784 @@
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
+use std::thread;
+use std::ptr::NonNull;
+use std::num::NonZeroU32;
+
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    wrapper::ConnectionExt as _,
+    properties::WmSizeHints,
+    protocol::{
+        shape::{self, ConnectionExt as _, SO, SK},
+        sync::{ConnectionExt as _, Int64},
+        present::{self, ConnectionExt as _},
+        xproto::{
+            self,
+            ConnectionExt as _,
+            WindowClass,
+            CreateWindowAux,
+            ConfigureWindowAux,
+            ClientMessageEvent,
+            StackMode,
+            ClipOrdering,
+            Rectangle,
+            PropMode,
+            EventMask
+        }
+    }
+};
+
+use raw_window_handle::{
+    XcbWindowHandle, XcbDisplayHandle,
+    WindowHandle, RawWindowHandle,
+    DisplayHandle, RawDisplayHandle
+};
+
+use crate::{
+    WindowConfig,
+    WindowId,
+    FrameExtents,
+    ResizeDirection,
+    Error,
+    dpi::{Position, Size, PhysicalSize, PhysicalPosition, Rect, ScaleFactorSource}
+};
+
+use super::{EventLoop, Shared, WindowState, SyncState, MonitorHandle};
+
+/// A handle to a window, which can be cloned and shared between threads
+/// 
+/// The window itself is only destroyed with [`Window::destroy()`], after which all
+/// handles return [`Error::WindowDestroyed`]
+#[derive(Clone)]
+pub struct Window {
+    shared: Arc<Shared>,
+    window: u32,
+    state: Arc<WindowState>
+}
+
+impl Window {
+    pub fn new<T>(event_loop: &EventLoop<T>, config: &WindowConfig) -> Result<Self, Error> {
+        let shared = event_loop.shared().clone();
+        let conn = &shared.conn;
+        let atoms = &shared.atoms;
+        let screen = &conn.setup().roots[shared.screen_num];
+        let scale_factor_override = config.scale_factor_override.filter(|scale_factor| scale_factor.is_finite() && *scale_factor > 0.0);
+
+        let (scale_factor, scale_factor_source) = match scale_factor_override {
+            Some(scale_factor) => (scale_factor, ScaleFactorSource::Override),
+            None => shared.primary_scale_factor()
+        };
+
+        // Calculate window physical size, clamped to what X11 can represent
+        let size = config.size.to_physical::<u16>(scale_factor).cast();
+
+        // Create window
+        let window = conn.generate_id().map_err(Error::X11GenerateIdFailed)?;
+
+        let event_mask = EventMask::KEY_PRESS |
+                         EventMask::KEY_RELEASE |
+                         EventMask::ENTER_WINDOW |
+                         EventMask::LEAVE_WINDOW |
+                         EventMask::POINTER_MOTION |
+                         EventMask::BUTTON_PRESS |
+                         EventMask::BUTTON_RELEASE |
+                         EventMask::STRUCTURE_NOTIFY |
+                         EventMask::EXPOSURE |
+                         EventMask::FOCUS_CHANGE |
+                         EventMask::PROPERTY_CHANGE;
+
+        let aux = CreateWindowAux::new()
+            .background_pixel(screen.black_pixel)
+            .event_mask(event_mask);
+
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            screen.root,
+            0,
+            0,
+            size.width as u16,
+            size.height as u16,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            0,
+            &aux
+        ).map_err(Error::X11CreateWindowFailed)?;
+
+        // Set title
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            xproto::AtomEnum::WM_NAME,
+            xproto::AtomEnum::STRING,
+            config.title.as_bytes()
+        ).map_err(Error::X11SetTitleFailed)?;
+
+        let mut protocols = vec![atoms.WM_DELETE_WINDOW];
+
+        // Set up counters for synchronized resizing with the window manager. The second
+        // counter enables the extended protocol, where the counter is odd while a frame is
+        // being drawn
+        let sync = if shared.sync_supported {
+            let basic_counter = conn.generate_id().map_err(Error::X11GenerateIdFailed)?;
+            let extended_counter = conn.generate_id().map_err(Error::X11GenerateIdFailed)?;
+
+            for counter in [basic_counter, extended_counter] {
+                conn
+                    .sync_create_counter(counter, Int64 { hi: 0, lo: 0 })
+                    .map_err(Error::X11SyncCounterFailed)?;
+            }
+
+            conn.change_property32(
+                PropMode::REPLACE,
+                window,
+                atoms._NET_WM_SYNC_REQUEST_COUNTER,
+                xproto::AtomEnum::CARDINAL,
+                &[basic_counter, extended_counter]
+            ).map_err(Error::X11SyncCounterFailed)?;
+
+            protocols.push(atoms._NET_WM_SYNC_REQUEST);
+
+            Some(SyncState {
+                basic_counter,
+                extended_counter,
+                request: Mutex::new(None),
+                frame_counter: Mutex::new(0),
+                manual: config.manual_frame_sync
+            })
+        }
+        else {
+            None
+        };
+
+        // Hook window close event and sync requests
+        conn.change_property32(
+            PropMode::REPLACE,
+            window,
+            atoms.WM_PROTOCOLS,
+            xproto::AtomEnum::ATOM,
+            &protocols
+        ).map_err(Error::X11WindowCloseHookFailed)?;
+
+        // Set size hints if not resizable
+        if !config.resizable {
+            let hints = WmSizeHints {
+                min_size: Some((size.width as i32, size.height as i32)),
+                max_size: Some((size.width as i32, size.height as i32)),
+                ..Default::default()
+            };
+
+            hints
+                .set_normal_hints(conn, window)
+                .map_err(Error::X11SetSizeHintsFailed)?;
+        }
+
+        // Ask the window manager to set _NET_FRAME_EXTENTS before the window is mapped,
+        // so the decoration size is known up front
+        let event = ClientMessageEvent::new(32, window, atoms._NET_REQUEST_FRAME_EXTENTS, [0; 5]);
+
+        conn.send_event(
+            false,
+            screen.root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event
+        ).map_err(Error::X11SendEventFailed)?;
+
+        // Show window if needed
+        if config.visible {
+            conn.map_window(window).map_err(Error::X11MapWindowFailed)?;
+        }
+
+        conn.flush().map_err(Error::X11FlushFailed)?;
+
+        // Register with the event loop so it can keep the window state updated
+        let state = Arc::new(WindowState {
+            size: Mutex::new(size),
+            scale_factor: Mutex::new((scale_factor, scale_factor_source)),
+            frame_extents: Mutex::new(FrameExtents::default()),
+            damage: Mutex::new(Vec::new()),
+            sync,
+            present_event: Mutex::new(None),
+            last_frame: Mutex::new(None),
+            destroyed: AtomicBool::new(false)
+        });
+
+        shared.windows.lock().unwrap().insert(window, state.clone());
+
+        Ok(Self {
+            shared,
+            window,
+            state
+        })
+    }
+
+    pub fn set_title(&self, title: &str) -> Result<(), Error> {
+        self.check_alive()?;
+
+        self.shared.conn.change_property8(
+            PropMode::REPLACE,
+            self.window,
+            xproto::AtomEnum::WM_NAME,
+            xproto::AtomEnum::STRING,
+            title.as_bytes()
+        ).map_err(Error::X11SetTitleFailed)?;
+
+        self.shared.conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    pub fn set_visible(&self, visible: bool) -> Result<(), Error> {
+        self.check_alive()?;
+
+        if visible {
+            self.shared.conn.map_window(self.window).map_err(Error::X11MapWindowFailed)?;
+        }
+        else {
+            self.shared.conn.unmap_window(self.window).map_err(Error::X11MapWindowFailed)?;
+        }
+
+        self.shared.conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    pub fn set_resizable(&self, resizable: bool) -> Result<(), Error> {
+        self.check_alive()?;
+
+        let hints = if !resizable {
+            let size = self.size()?;
+
+            WmSizeHints {
+                min_size: Some((size.width as i32, size.height as i32)),
+                max_size: Some((size.width as i32, size.height as i32)),
+                ..Default::default()
+            }
+        }
+        else {
+            WmSizeHints {
+                min_size: None,
+                max_size: None,
+                ..Default::default()
+            }
+        };
+
+        hints
+            .set_normal_hints(&self.shared.conn, self.window)
+            .map_err(Error::X11SetSizeHintsFailed)?;
+
+        self.shared.conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    pub fn size(&self) -> Result<PhysicalSize, Error> {
+        self.check_alive()?;
+
+        Ok(self.state.size())
+    }
+
+    pub fn outer_size(&self) -> Result<PhysicalSize, Error> {
+        let size = self.size()?;
+        let frame_extents = self.frame_extents()?;
+
+        Ok(PhysicalSize {
+            width: size.width + frame_extents.left + frame_extents.right,
+            height: size.height + frame_extents.top + frame_extents.bottom
+        })
+    }
+
+    pub fn frame_extents(&self) -> Result<FrameExtents, Error> {
+        self.check_alive()?;
+
+        Ok(*self.state.frame_extents.lock().unwrap())
+    }
+
+    pub fn scale_factor(&self) -> f32 {
+        self.state.scale_factor()
+    }
+
+    pub fn scale_factor_source(&self) -> ScaleFactorSource {
+        self.state.scale_factor_source()
+    }
+
+    pub fn request_redraw(&self) -> Result<(), Error> {
+        self.check_alive()?;
+
+        let rect = Rect { position: PhysicalPosition { x: 0, y: 0 }, size: self.state.size() };
+        let pending = self.state.add_damage(rect);
+
+        // `run()` and `pump_events()` check for redraws before waiting, so they only need
+        // to be woken up for requests from other threads. Reactors and executors only
+        // wake up when the connection becomes readable
+        if !pending && *self.shared.loop_thread.lock().unwrap() != Some(thread::current().id()) {
+            self.shared.wake_up()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn current_monitor(&self) -> Result<MonitorHandle, Error> {
+        self.check_alive()?;
+
+        let coords = self.shared.conn
+            .translate_coordinates(self.window, self.shared.root, 0, 0)
+            .map_err(|err| Error::X11TranslateCoordinatesFailed(err.into()))?
+            .reply()
+            .map_err(Error::X11TranslateCoordinatesFailed)?;
+
+        self.shared.wake_after_round_trip()?;
+
+        let size = self.state.size();
+
+        // There's always at least one monitor, as the whole screen is used as a fallback
+        let monitor = self.shared
+            .monitor_at(coords.dst_x as i32, coords.dst_y as i32, size.width, size.height)
+            .unwrap();
+
+        Ok(monitor)
+    }
+
+    pub fn frame_presented(&self) -> Result<(), Error> {
+        self.check_alive()?;
+
+        match &self.state.sync {
+            Some(sync) => sync.finish_frame(&self.shared.conn),
+            None => Ok(())
+        }
+    }
+
+    pub fn sync_request_serial(&self) -> Option<u64> {
+        let sync = self.state.sync.as_ref()?;
+        let request = sync.request.lock().unwrap();
+
+        request.map(|request| request.serial)
+    }
+
+    pub fn request_frame_callback(&self) -> Result<(), Error> {
+        if !self.shared.present_supported {
+            return Err(Error::X11ExtensionMissing(present::X11_EXTENSION_NAME));
+        }
+
+        self.check_alive()?;
+
+        // Completion events are only selected once they're first needed
+        let mut present_event = self.state.present_event.lock().unwrap();
+
+        if present_event.is_none() {
+            let event = self.shared.conn.generate_id().map_err(Error::X11GenerateIdFailed)?;
+
+            self.shared.conn
+                .present_select_input(event, self.window, present::EventMask::COMPLETE_NOTIFY)
+                .map_err(Error::X11PresentFailed)?;
+
+            *present_event = Some(event);
+        }
+
+        drop(present_event);
+
+        // Divisor 1 and remainder 0 completes at the next MSC, which is the next vblank
+        self.shared.conn
+            .present_notify_msc(self.window, 0, 0, 1, 0)
+            .map_err(Error::X11PresentFailed)?;
+
+        self.shared.conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    pub fn drag_window(&self) -> Result<(), Error> {
+        self.move_resize(8)
+    }
+
+    pub fn drag_resize_window(&self, direction: ResizeDirection) -> Result<(), Error> {
+        // Direction values from the _NET_WM_MOVERESIZE spec
+        let direction = match direction {
+            ResizeDirection::NorthWest => 0,
+            ResizeDirection::North => 1,
+            ResizeDirection::NorthEast => 2,
+            ResizeDirection::East => 3,
+            ResizeDirection::SouthEast => 4,
+            ResizeDirection::South => 5,
+            ResizeDirection::SouthWest => 6,
+            ResizeDirection::West => 7
+        };
+
+        self.move_resize(direction)
+    }
+
+    pub fn show_window_menu(&self, position: &Position) -> Result<(), Error> {
+        self.check_alive()?;
+
+        let position: PhysicalPosition<i16> = position.to_physical(self.state.scale_factor());
+
+        // The menu position is given in root window coordinates
+        let coords = self.shared.conn
+            .translate_coordinates(self.window, self.shared.root, position.x, position.y)
+            .map_err(|err| Error::X11TranslateCoordinatesFailed(err.into()))?
+            .reply()
+            .map_err(Error::X11TranslateCoordinatesFailed)?;
+
+        self.shared.wake_after_round_trip()?;
+
+        self.shared.conn.ungrab_pointer(x11rb::CURRENT_TIME).map_err(Error::X11UngrabPointerFailed)?;
+
+        self.send_wm_message(
+            self.shared.atoms._GTK_SHOW_WINDOW_MENU,
+            [0, coords.dst_x as u32, coords.dst_y as u32, 0, 0]
+        )
+    }
+
+    pub fn set_shape(&self, rects: Option<&[Rect]>) -> Result<(), Error> {
+        self.set_shape_region(SK::BOUNDING, rects)
+    }
+
+    pub fn set_input_region(&self, rects: Option<&[Rect]>) -> Result<(), Error> {
+        self.set_shape_region(SK::INPUT, rects)
+    }
+
+    pub fn set_opacity(&self, opacity: f32) -> Result<(), Error> {
+        self.check_alive()?;
+
+        // NaN would otherwise become fully transparent
+        let opacity = if opacity.is_nan() { 1.0 } else { opacity.clamp(0.0, 1.0) };
+        let opacity = (opacity as f64 * u32::MAX as f64).round() as u32;
+
+        self.shared.conn.change_property32(
+            PropMode::REPLACE,
+            self.window,
+            self.shared.atoms._NET_WM_WINDOW_OPACITY,
+            xproto::AtomEnum::CARDINAL,
+            &[opacity]
+        ).map_err(Error::X11SetOpacityFailed)?;
+
+        self.shared.conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    pub fn set_blur_behind(&self, rects: Option<&[Rect]>) -> Result<(), Error> {
+        self.check_alive()?;
+
+        match rects {
+            Some(rects) => {
+                // An empty region asks for the whole window to be blurred. The region is
+                // unsigned, so parts of rects left of or above the window are cut off
+                let region = rects
+                    .iter()
+                    .flat_map(|rect| {
+                        let x = rect.position.x.max(0);
+                        let y = rect.position.y.max(0);
+                        let width = rect.size.width.saturating_sub(x.abs_diff(rect.position.x));
+                        let height = rect.size.height.saturating_sub(y.abs_diff(rect.position.y));
+
+                        [x as u32, y as u32, width, height]
+                    })
+                    .collect::<Vec<_>>();
+
+                self.shared.conn.change_property32(
+                    PropMode::REPLACE,
+                    self.window,
+                    self.shared.atoms._KDE_NET_WM_BLUR_BEHIND_REGION,
+                    xproto::AtomEnum::CARDINAL,
+                    &region
+                ).map_err(Error::X11SetBlurFailed)?;
+            },
+
+            None => {
+                self.shared.conn
+                    .delete_property(self.window, self.shared.atoms._KDE_NET_WM_BLUR_BEHIND_REGION)
+                    .map_err(Error::X11SetBlurFailed)?;
+            }
+        }
+
+        self.shared.conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    fn set_shape_region(&self, kind: SK, rects: Option<&[Rect]>) -> Result<(), Error> {
+        self.check_alive()?;
+
+        let shape_ext = self.shared.conn
+            .extension_information(shape::X11_EXTENSION_NAME)
+            .map_err(Error::X11ExtensionQueryFailed)?;
+
+        if shape_ext.is_none() {
+            return Err(Error::X11ExtensionMissing(shape::X11_EXTENSION_NAME));
+        }
+
+        match rects {
+            Some(rects) => {
+                let rects = rects.iter().map(to_x11_rect).collect::<Vec<_>>();
+                self.shared.conn.shape_rectangles(SO::SET, kind, ClipOrdering::UNSORTED, self.window, 0, 0, &rects)
+            },
+
+            // Setting no mask restores the default rectangular region
+            None => self.shared.conn.shape_mask(SO::SET, kind, self.window, 0, 0, x11rb::NONE)
+        }.map_err(Error::X11SetShapeFailed)?;
+
+        self.shared.conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    fn move_resize(&self, direction: u32) -> Result<(), Error> {
+        self.check_alive()?;
+
+        // Use the button and pointer position from the button press that started the drag,
+        // falling back to the current pointer position if no button is held
+        let last_button_press = *self.shared.last_button_press.lock().unwrap();
+
+        let (root_x, root_y, button) = match last_button_press {
+            Some(press) => press,
+
+            None => {
+                let pointer = self.shared.conn
+                    .query_pointer(self.shared.root)
+                    .map_err(|err| Error::X11QueryPointerFailed(err.into()))?
+                    .reply()
+                    .map_err(Error::X11QueryPointerFailed)?;
+
+                self.shared.wake_after_round_trip()?;
+
+                (pointer.root_x, pointer.root_y, 1)
+            }
+        };
+
+        // The WM can't grab the pointer while our implicit grab from the button press is active
+        self.shared.conn.ungrab_pointer(x11rb::CURRENT_TIME).map_err(Error::X11UngrabPointerFailed)?;
+
+        // Source indication 1 means the request comes from a normal application
+        self.send_wm_message(
+            self.shared.atoms._NET_WM_MOVERESIZE,
+            [root_x as u32, root_y as u32, direction, button as u32, 1]
+        )
+    }
+
+    fn send_wm_message(&self, message_type: u32, data: [u32; 5]) -> Result<(), Error> {
+        let event = ClientMessageEvent::new(32, self.window, message_type, data);
+
+        self.shared.conn.send_event(
+            false,
+            self.shared.root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event
+        ).map_err(Error::X11SendEventFailed)?;
+
+        self.shared.conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    pub fn window_handle(&self) -> WindowHandle<'_> {
+        let window = NonZeroU32::new(self.window).unwrap();
+        let handle = RawWindowHandle::Xcb(XcbWindowHandle::new(window));
+
+        unsafe { WindowHandle::borrow_raw(handle) }
+    }
+
+    pub fn display_handle(&self) -> DisplayHandle<'_> {
+        let conn = NonNull::new(self.shared.conn.get_raw_xcb_connection());
+        let handle = RawDisplayHandle::Xcb(XcbDisplayHandle::new(conn, self.shared.screen_num as i32));
+
+        unsafe { DisplayHandle::borrow_raw(handle) }
+    }
+
+    pub fn create_child(&self, config: &WindowConfig, position: &Position, size: &Size) -> Result<ChildWindow, Error> {
+        ChildWindow::new(self, config, position, size)
+    }
+
+    pub fn id(&self) -> WindowId {
+        WindowId(self.window as u64)
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.state.destroyed.load(Ordering::Acquire)
+    }
+
+    pub fn destroy(&self) {
+        if self.state.destroyed.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        self.shared.windows.lock().unwrap().remove(&self.window);
+
+        if let Some(sync) = &self.state.sync {
+            self.shared.conn.sync_destroy_counter(sync.basic_counter).unwrap();
+            self.shared.conn.sync_destroy_counter(sync.extended_counter).unwrap();
+        }
+
+        self.shared.conn.destroy_window(self.window).unwrap();
+        self.shared.conn.flush().unwrap();
+    }
+
+    fn check_alive(&self) -> Result<(), Error> {
+        match self.is_destroyed() {
+            true => Err(Error::WindowDestroyed),
+            false => Ok(())
+        }
+    }
+}
+
+pub struct ChildWindow {
+    parent: Window,
+    window: u32,
+    size: Mutex<PhysicalSize>
+}
+
+impl ChildWindow {
+    fn new(parent: &Window, config: &WindowConfig, position: &Position, size: &Size) -> Result<Self, Error> {
+        parent.check_alive()?;
+
+        let conn = &parent.shared.conn;
+
+        // Calculate child geometry using the parent's scale factor, clamped to what X11
+        // can represent
+        let position: PhysicalPosition<i16> = position.to_physical(parent.state.scale_factor());
+        let size: PhysicalSize<u16> = size.to_physical(parent.state.scale_factor());
+
+        // Create window
+        let window = conn.generate_id().map_err(Error::X11GenerateIdFailed)?;
+
+        let event_mask = EventMask::KEY_PRESS |
+                         EventMask::KEY_RELEASE |
+                         EventMask::ENTER_WINDOW |
+                         EventMask::LEAVE_WINDOW |
+                         EventMask::POINTER_MOTION |
+                         EventMask::BUTTON_PRESS |
+                         EventMask::BUTTON_RELEASE |
+                         EventMask::FOCUS_CHANGE;
+
+        let aux = CreateWindowAux::new()
+            .background_pixel(conn.setup().roots[parent.shared.screen_num].black_pixel)
+            .event_mask(event_mask);
+
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            parent.window,
+            position.x,
+            position.y,
+            size.width,
+            size.height,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            0,
+            &aux
+        ).map_err(Error::X11CreateWindowFailed)?;
+
+        // Set title, useful for debugging tools such as xwininfo
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            xproto::AtomEnum::WM_NAME,
+            xproto::AtomEnum::STRING,
+            config.title.as_bytes()
+        ).map_err(Error::X11SetTitleFailed)?;
+
+        // Show window if needed
+        if config.visible {
+            conn.map_window(window).map_err(Error::X11MapWindowFailed)?;
+        }
+
+        conn.flush().map_err(Error::X11FlushFailed)?;
+
+        Ok(Self {
+            parent: parent.clone(),
+            window,
+            size: Mutex::new(size.cast())
+        })
+    }
+
+    pub fn id(&self) -> WindowId {
+        WindowId(self.window as u64)
+    }
+
+    pub fn set_visible(&self, visible: bool) -> Result<(), Error> {
+        self.parent.check_alive()?;
+
+        let conn = &self.parent.shared.conn;
+
+        if visible {
+            conn.map_window(self.window).map_err(Error::X11MapWindowFailed)?;
+        }
+        else {
+            conn.unmap_window(self.window).map_err(Error::X11MapWindowFailed)?;
+        }
+
+        conn.flush().map_err(Error::X11FlushFailed)
+    }
+
+    pub fn set_position(&self, position: &Position) -> Result<(), Error> {
+        self.parent.check_alive()?;
+
+        let position: PhysicalPosition<i16> = position.to_physical(self.parent.state.scale_factor());
+
+        let aux = ConfigureWindowAux::new()
+            .x(i32::from(position.x))
+            .y(i32::from(position.y));
+
+        self.configure(&aux)
+    }
+
+    pub fn set_size(&self, size: &Size) -> Result<(), Error> {
+        self.parent.check_alive()?;
+
+        let size: PhysicalSize = size.to_physical::<u16>(self.parent.state.scale_factor()).cast();
+
+        let aux = ConfigureWindowAux::new()
+            .width(size.width)
+            .height(size.height);
+
+        self.configure(&aux)?;
+        *self.size.lock().unwrap() = size;
+
+        Ok(())
+    }
+
+    pub fn size(&self) -> PhysicalSize {
+        *self.size.lock().unwrap()
+    }
+
+    pub fn raise(&self) -> Result<(), Error> {
+        self.parent.check_alive()?;
+        self.configure(&ConfigureWindowAux::new().stack_mode(StackMode::ABOVE))
+    }
+
+    pub fn lower(&self) -> Result<(), Error> {
+        self.parent.check_alive()?;
+        self.configure(&ConfigureWindowAux::new().stack_mode(StackMode::BELOW))
+    }
+
+    /// Whether the child window was destroyed along with its parent
+    pub fn is_destroyed(&self) -> bool {
+        self.parent.is_destroyed()
+    }
+
+    pub fn window_handle(&self) -> WindowHandle<'_> {
+        let window = NonZeroU32::new(self.window).unwrap();
+        let handle = RawWindowHandle::Xcb(XcbWindowHandle::new(window));
+
+        unsafe { WindowHandle::borrow_raw(handle) }
+    }
+
+    pub fn display_handle(&self) -> DisplayHandle<'_> {
+        self.parent.display_handle()
+    }
+
+    fn configure(&self, aux: &ConfigureWindowAux) -> Result<(), Error> {
+        let conn = &self.parent.shared.conn;
+
+        conn.configure_window(self.window, aux).map_err(Error::X11ConfigureWindowFailed)?;
+        conn.flush().map_err(Error::X11FlushFailed)
+    }
+}
+
+impl Drop for ChildWindow {
+    fn drop(&mut self) {
+        // Destroying the parent already destroyed the child
+        if self.parent.is_destroyed() {
+            return;
+        }
+
+        self.parent.shared.conn.destroy_window(self.window).unwrap();
+        self.parent.shared.conn.flush().unwrap();
+    }
+}
+
+/// Converts a rect to an X11 rectangle, clamping it to what X11 can represent
+fn to_x11_rect(rect: &Rect) -> Rectangle {
+    let position = rect.position.cast::<i16>();
+    let size = rect.size.cast::<u16>();
+
+    Rectangle {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height
+    }
+}
@@ -0,0 +1,145 @@
+//! Monitor tests against a virtual X server
+//! 
+//! These start `Xvfb` on a spare display, so they're ignored by default. Run them with
+//! `cargo test --test xvfb -- --ignored`
+
+#![cfg(unix)]
+
+use std::os::unix::net::UnixStream;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use swil::EventLoop;
+use swil::dpi::{PhysicalPosition, PhysicalSize};
+
+// The event loop connects to `DISPLAY`, which is shared by all tests
+static DISPLAY_LOCK: Mutex<()> = Mutex::new(());
+
+struct Xvfb {
+    process: Child
+}
+
+impl Drop for Xvfb {
+    fn drop(&mut self) {
+        // Terminate gracefully so the server cleans up its socket and lock file
+        unsafe {
+            libc::kill(self.process.id() as i32, libc::SIGTERM);
+        }
+
+        let _ = self.process.wait();
+    }
+}
+
+/// Starts `Xvfb` on the given display and points `DISPLAY` at it
+/// 
+/// Panics if `Xvfb` isn't installed or doesn't start
+fn start_xvfb(display: u32, args: &[&str]) -> Xvfb {
+    // Don't mistake another server for ours
+    let socket = format!("/tmp/.X11-unix/X{display}");
+    assert!(UnixStream::connect(&socket).is_err(), "display :{display} is already in use");
+
+    let process = Command::new("Xvfb")
+        .arg(format!(":{display}"))
+        .args(["-screen", "0", "1920x1080x24", "-nolisten", "tcp"])
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to run Xvfb, is it installed?");
+
+    let mut xvfb = Xvfb { process };
+
+    // Wait until the server accepts connections. A socket left behind by a crashed
+    // server exists but refuses connections
+    let deadline = Instant::now() + Duration::from_secs(10);
+
+    loop {
+        if let Ok(Some(status)) = xvfb.process.try_wait() {
+            panic!("Xvfb exited while starting on :{display} with {status}");
+        }
+
+        if UnixStream::connect(&socket).is_ok() {
+            break;
+        }
+
+        assert!(Instant::now() < deadline, "Xvfb didn't start on :{display} in time");
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    std::env::set_var("DISPLAY", format!(":{display}"));
+
+    xvfb
+}
+
+#[test]
+#[ignore = "requires Xvfb"]
+fn randr_monitors() {
+    let _lock = DISPLAY_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+
+    let _xvfb = start_xvfb(91, &[]);
+
+    let event_loop = EventLoop::<()>::new().unwrap();
+    let monitors = event_loop.available_monitors();
+
+    assert_eq!(monitors.len(), 1);
+
+    let monitor = &monitors[0];
+    assert_eq!(monitor.position(), PhysicalPosition::new(0, 0));
+    assert_eq!(monitor.size(), PhysicalSize::new(1920, 1080));
+    assert!(monitor.scale_factor() > 0.0);
+
+    // Without a window manager the work area is the whole monitor
+    let work_area = monitor.work_area();
+    assert_eq!(work_area.position, monitor.position());
+    assert_eq!(work_area.size, monitor.size());
+
+    let primary = event_loop.primary_monitor().unwrap();
+    assert_eq!(primary, *monitor);
+}
+
+#[test]
+#[ignore = "requires Xvfb"]
+fn screen_fallback_without_randr() {
+    let _lock = DISPLAY_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+
+    let _xvfb = start_xvfb(92, &["-extension", "RANDR"]);
+
+    let event_loop = EventLoop::<()>::new().unwrap();
+    let monitors = event_loop.available_monitors();
+
+    // The whole screen is treated as a single monitor
+    assert_eq!(monitors.len(), 1);
+
+    let monitor = &monitors[0];
+    assert_eq!(monitor.name(), "default");
+    assert!(monitor.is_primary());
+    assert_eq!(monitor.position(), PhysicalPosition::new(0, 0));
+    assert_eq!(monitor.size(), PhysicalSize::new(1920, 1080));
+    assert!(monitor.video_modes().is_empty());
+}
+
+#[test]
+#[ignore = "requires Xvfb"]
+fn xinerama_monitors() {
+    let _lock = DISPLAY_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+
+    // Xinerama is only active with more than one screen
+    let _xvfb = start_xvfb(93, &["-screen", "1", "1280x1024x24", "+xinerama"]);
+
+    let event_loop = EventLoop::<()>::new().unwrap();
+    let monitors = event_loop.available_monitors();
+
+    assert_eq!(monitors.len(), 2);
+
+    assert_eq!(monitors[0].name(), "Xinerama-0");
+    assert!(monitors[0].is_primary());
+    assert_eq!(monitors[0].position(), PhysicalPosition::new(0, 0));
+    assert_eq!(monitors[0].size(), PhysicalSize::new(1920, 1080));
+
+    assert_eq!(monitors[1].name(), "Xinerama-1");
+    assert!(!monitors[1].is_primary());
+    assert_eq!(monitors[1].position(), PhysicalPosition::new(1920, 0));
+    assert_eq!(monitors[1].size(), PhysicalSize::new(1280, 1024));
+}